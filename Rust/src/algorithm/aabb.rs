@@ -0,0 +1,107 @@
+use super::interval::Interval;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+  pub min: Vec3,
+  pub max: Vec3,
+}
+
+impl Aabb {
+  pub fn new(a: Vec3, b: Vec3) -> Aabb {
+    Aabb {
+      min: Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+      max: Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+    }
+  }
+
+  pub fn empty() -> Aabb {
+    Aabb {
+      min: Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+      max: Vec3::new(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+    }
+  }
+
+  pub fn union(&self, other: &Aabb) -> Aabb {
+    Aabb {
+      min: Vec3::new(
+        self.min.x.min(other.min.x),
+        self.min.y.min(other.min.y),
+        self.min.z.min(other.min.z),
+      ),
+      max: Vec3::new(
+        self.max.x.max(other.max.x),
+        self.max.y.max(other.max.y),
+        self.max.z.max(other.max.z),
+      ),
+    }
+  }
+
+  pub fn point(point: Vec3) -> Aabb {
+    Aabb {
+      min: point,
+      max: point,
+    }
+  }
+
+  pub fn pad(&self, delta: f64) -> Aabb {
+    let padding = Vec3::new(delta, delta, delta);
+    Aabb {
+      min: self.min - 0.5 * padding,
+      max: self.max + 0.5 * padding,
+    }
+  }
+
+  pub fn size(&self) -> Vec3 {
+    self.max - self.min
+  }
+
+  pub fn centroid(&self) -> Vec3 {
+    0.5 * (self.min + self.max)
+  }
+
+  pub fn surface_area(&self) -> f64 {
+    let size = self.size();
+    2. * (size.x * size.y + size.x * size.z + size.y * size.z)
+  }
+
+  pub fn bounding_sphere(&self) -> (Vec3, f64) {
+    let center = self.centroid();
+    (center, 0.5 * self.size().len())
+  }
+
+  pub fn contains(&self, point: Vec3) -> bool {
+    (self.min.x..=self.max.x).contains(&point.x)
+      && (self.min.y..=self.max.y).contains(&point.y)
+      && (self.min.z..=self.max.z).contains(&point.z)
+  }
+
+  pub fn local(&self, point: Vec3) -> Vec3 {
+    let size = self.size();
+    let offset = point - self.min;
+    Vec3::new(offset.x / size.x, offset.y / size.y, offset.z / size.z)
+  }
+
+  pub fn hit(&self, ray: Ray, interval: Interval) -> Option<(f64, f64)> {
+    let origin = [ray.origin.x, ray.origin.y, ray.origin.z];
+    let direction = [ray.direction.x, ray.direction.y, ray.direction.z];
+    let min = [self.min.x, self.min.y, self.min.z];
+    let max = [self.max.x, self.max.y, self.max.z];
+    let (mut t0, mut t1) = (interval.min, interval.max);
+    for axis in 0..3 {
+      let inv_direction = 1. / direction[axis];
+      let mut near = (min[axis] - origin[axis]) * inv_direction;
+      let mut far = (max[axis] - origin[axis]) * inv_direction;
+      if near > far {
+        std::mem::swap(&mut near, &mut far);
+      }
+      t0 = if near > t0 { near } else { t0 };
+      t1 = if far < t1 { far } else { t1 };
+      if t1 <= t0 {
+        return None;
+      }
+    }
+    Some((t0, t1))
+  }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::aabb::Aabb;
+use super::color::{Color, luminance};
+use super::direction_cone::DirectionCone;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::random;
+use super::ray::Ray;
+use super::texture::{SolidColor, Texture};
+use super::vec3::Vec3;
+
+const MAX_LAYERS: usize = 64;
+
+pub struct AlphaMask {
+  pub object: Arc<dyn Hittable>,
+  pub opacity: Arc<dyn Texture>,
+}
+
+impl AlphaMask {
+  pub fn new(object: Arc<dyn Hittable>, opacity: Arc<dyn Texture>) -> AlphaMask {
+    AlphaMask { object, opacity }
+  }
+
+  pub fn with_opacity(object: Arc<dyn Hittable>, opacity: f64) -> AlphaMask {
+    Self::new(object, Arc::new(SolidColor::new(Color::new(opacity, opacity, opacity))))
+  }
+
+  pub fn from_material(object: Arc<dyn Hittable>) -> AlphaMask {
+    Self::with_opacity(object, 1.)
+  }
+}
+
+impl Hittable for AlphaMask {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    let mut rng = random::rng();
+    let mut min = interval.min;
+    for _ in 0..MAX_LAYERS {
+      let record = self.object.hit(ray, Interval::new(min, interval.max))?;
+      let opacity = luminance(self.opacity.value(record.u, record.v, record.point))
+        * record.material.opacity(&record);
+      if opacity >= 1. || (opacity > 0. && rng.random::<f64>() < opacity) {
+        return Some(record);
+      }
+      min = record.t + 1e-6 * record.t.abs().max(1.);
+    }
+    None
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    self.object.pdf_value(origin, direction)
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    self.object.random(origin)
+  }
+
+  fn area(&self) -> f64 {
+    self.object.area()
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    self.object.bounding_box()
+  }
+
+  fn normal_bounds(&self) -> DirectionCone {
+    self.object.normal_bounds()
+  }
+
+  fn emission(&self) -> Color {
+    self.object.emission()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::material::{Lambertian, Principled};
+  use crate::algorithm::quad::Quad;
+  use crate::algorithm::sphere::Sphere;
+
+  fn sphere() -> Arc<dyn Hittable> {
+    Arc::new(Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::default())))
+  }
+
+  #[test]
+  fn transparent_surfaces_are_skipped() {
+    let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+    let hidden = AlphaMask::with_opacity(sphere(), 0.);
+    assert!(
+      hidden
+        .hit(ray, Interval::new(0.001, f64::INFINITY))
+        .is_none()
+    );
+    let solid = AlphaMask::with_opacity(sphere(), 1.);
+    let record = solid.hit(ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+    assert!((record.t - 4.).abs() < 1e-9);
+  }
+
+  #[test]
+  fn material_opacity_masks_the_surface() {
+    let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -1.));
+    let interval = || Interval::new(0.001, f64::INFINITY);
+    let leaf = |opacity: f64| {
+      let material = Principled {
+        opacity,
+        ..Principled::default()
+      };
+      let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::zero(), 1., Arc::new(material)));
+      AlphaMask::from_material(sphere)
+    };
+    assert!(leaf(0.).hit(ray, interval()).is_none());
+    assert!(leaf(1.).hit(ray, interval()).is_some());
+    let half = leaf(0.5);
+    let samples = 10000;
+    let hits = (0..samples)
+      .filter(|_| half.hit(ray, interval()).is_some())
+      .count();
+    assert!((hits as f64 / samples as f64 - 0.75).abs() < 0.02);
+  }
+
+  #[test]
+  fn light_sampling_queries_reach_the_masked_shape() {
+    let quad: Arc<dyn Hittable> = Arc::new(Quad::new(
+      Vec3::new(-1., -1., 0.),
+      Vec3::new(2., 0., 0.),
+      Vec3::new(0., 2., 0.),
+      Arc::new(Lambertian::default()),
+    ));
+    let masked = AlphaMask::with_opacity(quad.clone(), 0.5);
+    let origin = Vec3::new(0., 0., 2.);
+    let direction = Vec3::new(0.1, 0.2, -1.);
+    assert_eq!(masked.area(), 4.);
+    assert_eq!(masked.pdf_value(origin, direction), quad.pdf_value(origin, direction));
+    let target = origin + masked.random(origin);
+    assert!(target.z.abs() < 1e-12 && target.x.abs() <= 1. && target.y.abs() <= 1.);
+    assert_eq!(masked.normal_bounds().cos_theta, quad.normal_bounds().cos_theta);
+  }
+}
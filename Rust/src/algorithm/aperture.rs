@@ -0,0 +1,200 @@
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::distribution::Distribution2D;
+use super::random;
+use super::vec3::Vec3;
+
+const MAX_MASK_DIMENSION: usize = 1 << 14;
+
+#[derive(Clone, Default)]
+pub enum Aperture {
+  #[default]
+  Circular,
+  Polygon {
+    blades: usize,
+    rotation: f64,
+  },
+  Mask(Arc<ApertureMask>),
+}
+
+impl Aperture {
+  pub fn sample(&self) -> Vec3 {
+    match self {
+      Aperture::Circular => Vec3::random_in_unit_disk(),
+      Aperture::Polygon { blades, .. } if *blades < 3 => Vec3::random_in_unit_disk(),
+      Aperture::Polygon { blades, rotation } => {
+        let mut rng = random::rng();
+        let blade = rng.random_range(0..*blades) as f64;
+        let vertex = |k: f64| {
+          let angle = rotation.to_radians() + 2. * PI * k / *blades as f64;
+          Vec3::new(angle.cos(), angle.sin(), 0.)
+        };
+        let (mut a, mut b) = (rng.random::<f64>(), rng.random::<f64>());
+        if a + b > 1. {
+          (a, b) = (1. - a, 1. - b);
+        }
+        a * vertex(blade) + b * vertex(blade + 1.)
+      }
+      Aperture::Mask(mask) => mask.sample(),
+    }
+  }
+}
+
+pub struct ApertureMask {
+  distribution: Distribution2D,
+  aspect: (f64, f64),
+}
+
+impl ApertureMask {
+  pub fn new(width: usize, height: usize, values: &[f64]) -> io::Result<ApertureMask> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if !(1..=MAX_MASK_DIMENSION).contains(&width) || !(1..=MAX_MASK_DIMENSION).contains(&height) {
+      return Err(invalid("aperture mask dimensions out of range"));
+    }
+    if values.len() != width * height {
+      return Err(invalid("aperture mask size does not match dimensions"));
+    }
+    if values.iter().any(|value| !value.is_finite() || *value < 0.) {
+      return Err(invalid("aperture mask values must be finite and non-negative"));
+    }
+    if values.iter().all(|value| *value == 0.) {
+      return Err(invalid("aperture mask is fully opaque"));
+    }
+    let longest = width.max(height) as f64;
+    Ok(ApertureMask {
+      distribution: Distribution2D::new(values, width, height),
+      aspect: (width as f64 / longest, height as f64 / longest),
+    })
+  }
+
+  pub fn load_pgm<P: AsRef<Path>>(path: P) -> io::Result<ApertureMask> {
+    Self::parse_pgm(&fs::read(path)?)
+  }
+
+  pub fn parse_pgm(bytes: &[u8]) -> io::Result<ApertureMask> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut position = 0;
+    let number = |position: &mut usize| {
+      pgm_token(bytes, position)
+        .and_then(|token| token.parse::<usize>().ok())
+        .ok_or_else(|| invalid("malformed PGM header"))
+    };
+
+    let magic = pgm_token(bytes, &mut position).unwrap_or_default();
+    let width = number(&mut position)?;
+    let height = number(&mut position)?;
+    let max_value = number(&mut position)?;
+    if max_value == 0 || max_value > u16::MAX as usize {
+      return Err(invalid("PGM maximum value out of range"));
+    }
+    if !(1..=MAX_MASK_DIMENSION).contains(&width) || !(1..=MAX_MASK_DIMENSION).contains(&height) {
+      return Err(invalid("PGM dimensions out of range"));
+    }
+    let count = width * height;
+
+    let values = match magic {
+      "P5" => {
+        let data = bytes.get(position + 1..).unwrap_or_default();
+        let size = if max_value > 255 { 2 } else { 1 };
+        if data.len() < count * size {
+          return Err(invalid("truncated PGM pixel data"));
+        }
+        data
+          .chunks_exact(size)
+          .take(count)
+          .map(|chunk| match chunk {
+            [high, low] => u16::from_be_bytes([*high, *low]) as f64,
+            [value] => *value as f64,
+            _ => 0.,
+          })
+          .collect::<Vec<_>>()
+      }
+      "P2" => (0..count)
+        .map(|_| number(&mut position).map(|value| value as f64))
+        .collect::<io::Result<Vec<_>>>()?,
+      _ => return Err(invalid("only P2 and P5 graymaps are supported")),
+    };
+    let values: Vec<f64> = values
+      .iter()
+      .map(|value| value / max_value as f64)
+      .collect();
+    Self::new(width, height, &values)
+  }
+
+  pub fn sample(&self) -> Vec3 {
+    let mut rng = random::rng();
+    let (x, y, _) = self.distribution.sample(rng.random(), rng.random());
+    let (sx, sy) = self.aspect;
+    Vec3::new(sx * (2. * x - 1.), sy * (1. - 2. * y), 0.)
+  }
+}
+
+fn pgm_token<'a>(bytes: &'a [u8], position: &mut usize) -> Option<&'a str> {
+  loop {
+    match bytes.get(*position)? {
+      b'#' => {
+        while bytes.get(*position).is_some_and(|&b| b != b'\n') {
+          *position += 1;
+        }
+      }
+      b if b.is_ascii_whitespace() => *position += 1,
+      _ => break,
+    }
+  }
+  let start = *position;
+  while bytes
+    .get(*position)
+    .is_some_and(|b| !b.is_ascii_whitespace())
+  {
+    *position += 1;
+  }
+  std::str::from_utf8(&bytes[start..*position]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_graymaps() {
+    let ascii = ApertureMask::parse_pgm(b"P2\n# star\n3 1\n255\n0 255 0\n").unwrap();
+    let point = ascii.sample();
+    assert!(point.x.abs() <= 1. / 3. + 1e-9 && point.y.abs() <= 1. / 3. + 1e-9);
+    let binary = ApertureMask::parse_pgm(b"P5 2 2 255\n\x00\x10\x20\x30").unwrap();
+    assert!(binary.sample().x.abs() <= 1.);
+  }
+
+  #[test]
+  fn wide_masks_keep_their_aspect_ratio() {
+    let mask = ApertureMask::new(4, 1, &[1.; 4]).unwrap();
+    for _ in 0..256 {
+      let point = mask.sample();
+      assert!(point.x.abs() <= 1. && point.y.abs() <= 0.25);
+    }
+  }
+
+  #[test]
+  fn rejects_malformed_graymaps() {
+    let invalid = |bytes: &[u8]| {
+      ApertureMask::parse_pgm(bytes)
+        .err()
+        .map(|error| error.kind())
+    };
+    let invalid_data = Some(io::ErrorKind::InvalidData);
+    assert_eq!(invalid(b"P2 0 100000000000 255"), invalid_data);
+    assert_eq!(invalid(b"P2 0 4 255\n"), invalid_data);
+    assert_eq!(invalid(b"P2 2 2 255\n0 0 0 0\n"), invalid_data);
+    assert_eq!(invalid(b"P2 2 2 0\n1 1 1 1\n"), invalid_data);
+    assert_eq!(invalid(b"P2 2 2 255\n1 1 1\n"), invalid_data);
+    assert_eq!(invalid(b"P5 2 2 255\n\x01\x02"), invalid_data);
+    assert_eq!(invalid(b"P6 1 1 255\n\x01\x02\x03"), invalid_data);
+    assert!(ApertureMask::new(2, 2, &[1.; 3]).is_err());
+    assert!(ApertureMask::new(1, 1, &[-1.]).is_err());
+  }
+}
@@ -0,0 +1,35 @@
+use super::color::Color;
+use super::ray::Ray;
+
+pub trait Background: Send + Sync {
+  fn color(&self, ray: &Ray) -> Color;
+}
+
+impl Background for Color {
+  fn color(&self, _: &Ray) -> Color {
+    *self
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GradientSky {
+  pub horizon: Color,
+  pub zenith: Color,
+}
+
+impl Default for GradientSky {
+  fn default() -> Self {
+    GradientSky {
+      horizon: Color::one(),
+      zenith: Color::new(0.5, 0.7, 1.0),
+    }
+  }
+}
+
+impl Background for GradientSky {
+  fn color(&self, ray: &Ray) -> Color {
+    let unit_direction = ray.direction.normalization();
+    let t = 0.5 * (unit_direction.y + 1.0);
+    (1.0 - t) * self.horizon + t * self.zenith
+  }
+}
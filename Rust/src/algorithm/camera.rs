@@ -1,12 +1,108 @@
-use rand::{Rng, rngs::ThreadRng};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::{ThreadPool, ThreadPoolBuildError};
 
+use super::aperture::Aperture;
+use super::background::{Background, GradientSky};
 use super::color::{Color, color_to_byte};
-use super::hittable::Hittable;
+use super::hittable::{HitRecord, Hittable};
 use super::interval::Interval;
+use super::lens::RealisticLens;
+use super::light::LightList;
+use super::material::ScatterSample;
+use super::random::{self, LocalRng};
 use super::ray::Ray;
+use super::spectrum::{self, Wavelengths};
 use super::vec3::Vec3;
 
+#[derive(Clone, Default)]
+pub enum Parallelism {
+  #[default]
+  Global,
+  Pool(Arc<ThreadPool>),
+  Serial,
+}
+
+impl Parallelism {
+  pub fn threads(threads: usize) -> Result<Parallelism, ThreadPoolBuildError> {
+    let pool = rayon::ThreadPoolBuilder::new()
+      .num_threads(threads)
+      .build()?;
+    Ok(Parallelism::Pool(Arc::new(pool)))
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FisheyeMapping {
+  #[default]
+  Equidistant,
+  Equisolid,
+}
+
+#[derive(Debug, Clone, Default)]
+pub enum Projection {
+  #[default]
+  Perspective,
+  Orthographic,
+  Equirectangular,
+  Fisheye(FisheyeMapping),
+  Cylindrical,
+  Realistic(Arc<RealisticLens>),
+}
+
+impl PartialEq for Projection {
+  fn eq(&self, other: &Projection) -> bool {
+    match (self, other) {
+      (Projection::Fisheye(a), Projection::Fisheye(b)) => a == b,
+      (Projection::Realistic(a), Projection::Realistic(b)) => Arc::ptr_eq(a, b),
+      _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StereoLayout {
+  #[default]
+  SideBySide,
+  TopBottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stereo {
+  layout: StereoLayout,
+  interocular_distance: f64,
+  convergence_distance: f64,
+}
+
+impl Stereo {
+  pub fn new(
+    layout: StereoLayout,
+    interocular_distance: f64,
+    convergence_distance: f64,
+  ) -> Option<Stereo> {
+    (interocular_distance.is_finite() && convergence_distance > 0.).then_some(Stereo {
+      layout,
+      interocular_distance,
+      convergence_distance,
+    })
+  }
+
+  pub fn layout(&self) -> StereoLayout {
+    self.layout
+  }
+
+  pub fn interocular_distance(&self) -> f64 {
+    self.interocular_distance
+  }
+
+  pub fn convergence_distance(&self) -> f64 {
+    self.convergence_distance
+  }
+}
+
 pub struct Camera {
   pub width: usize,
   pub height: usize,
@@ -18,6 +114,13 @@ pub struct Camera {
   pub vup: Vec3,
   pub defocus_angle: f64,
   pub focus_dist: f64,
+  pub parallelism: Parallelism,
+  pub seed: Option<u64>,
+  pub spectral: bool,
+  pub background: Arc<dyn Background>,
+  pub projection: Projection,
+  pub stereo: Option<Stereo>,
+  pub aperture: Aperture,
 
   center: Vec3,
   u: Vec3,
@@ -52,6 +155,13 @@ impl Camera {
       look_at,
       defocus_angle: 0.,
       focus_dist: 10.,
+      parallelism: Parallelism::Global,
+      seed: None,
+      spectral: false,
+      background: Arc::new(GradientSky::default()),
+      projection: Projection::Perspective,
+      stereo: None,
+      aperture: Aperture::Circular,
 
       u: Vec3::zero(),
       v: Vec3::zero(),
@@ -99,57 +209,165 @@ impl Camera {
     self.defocus_disk_v = self.v * defocus_radius;
   }
 
-  pub fn render<T>(&mut self, world: &T) -> Vec<u8>
+  pub fn output_size(&self) -> (usize, usize) {
+    match self.stereo.map(|stereo| stereo.layout()) {
+      Some(StereoLayout::SideBySide) => (2 * self.width, self.height),
+      Some(StereoLayout::TopBottom) => (self.width, 2 * self.height),
+      None => (self.width, self.height),
+    }
+  }
+
+  pub fn render<T>(&mut self, world: &T, lights: &LightList) -> Vec<u8>
   where
-    T: Hittable + Sync,
+    T: Hittable,
   {
     self.initialize();
-    let rows: Vec<u8> = (0..self.height)
-      .into_par_iter()
-      .flat_map(|j| {
-        let mut row_data = Vec::with_capacity(self.width * 4);
-        let mut rng = rand::rng();
-        for i in 0..self.width {
-          let mut pixel_color = Color::zero();
-          for _ in 0..self.samples_per_pixel {
-            let ray = self.ray(&mut rng, i as f64, j as f64);
-            pixel_color += Self::ray_color(ray, self.max_depth, world);
-          }
-          let (r, g, b) = color_to_byte(self.pixel_samples_scale * pixel_color);
-          row_data.extend_from_slice(&[r, g, b, 0xFF]);
+    match &self.parallelism {
+      Parallelism::Global => self.render_rows(world, lights),
+      Parallelism::Pool(pool) => pool.install(|| self.render_rows(world, lights)),
+      Parallelism::Serial => {
+        let rows = (0..self.output_size().1)
+          .flat_map(|j| self.render_row(world, lights, j, self.seed))
+          .collect();
+        if self.seed.is_some() {
+          random::reseed();
         }
-        row_data
-      })
+        rows
+      }
+    }
+  }
+
+  fn render_rows<T>(&self, world: &T, lights: &LightList) -> Vec<u8>
+  where
+    T: Hittable,
+  {
+    let rows = (0..self.output_size().1)
+      .into_par_iter()
+      .flat_map(|j| self.render_row(world, lights, j, self.seed))
       .collect();
+    if self.seed.is_some() {
+      rayon::broadcast(|_| random::reseed());
+    }
     rows
   }
 
-  fn ray(&self, rng: &mut ThreadRng, i: f64, j: f64) -> Ray {
+  fn render_row<T>(&self, world: &T, lights: &LightList, j: usize, seed: Option<u64>) -> Vec<u8>
+  where
+    T: Hittable,
+  {
+    if let Some(seed) = seed {
+      random::seed(seed ^ (j as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    }
+    let (output_width, _) = self.output_size();
+    let mut row_data = Vec::with_capacity(output_width * 4);
+    let mut rng = random::rng();
+    for x in 0..output_width {
+      let (eye, i, j) = match self.stereo.map(|stereo| stereo.layout()) {
+        Some(StereoLayout::SideBySide) if x >= self.width => (1., x - self.width, j),
+        Some(StereoLayout::TopBottom) if j >= self.height => (1., x, j - self.height),
+        Some(_) => (-1., x, j),
+        None => (0., x, j),
+      };
+      let mut pixel_color = Color::zero();
+      for _ in 0..self.samples_per_pixel {
+        let Some((ray, weight)) = self.ray(&mut rng, i as f64, j as f64, eye) else {
+          continue;
+        };
+        pixel_color += if self.spectral {
+          let wavelengths = Wavelengths::sample(rng.random::<f64>());
+          let ray = Ray::with_wavelengths(ray.origin, ray.direction, wavelengths);
+          let radiance = self.ray_color(ray, self.max_depth, world, lights, None);
+          weight * spectrum::xyz_to_linear_srgb(wavelengths.to_xyz(radiance))
+        } else {
+          weight * self.ray_color(ray, self.max_depth, world, lights, None)
+        };
+      }
+      let (r, g, b) = color_to_byte(self.pixel_samples_scale * pixel_color);
+      row_data.extend_from_slice(&[r, g, b, 0xFF]);
+    }
+    row_data
+  }
+
+  fn ray(&self, rng: &mut LocalRng, i: f64, j: f64, eye: f64) -> Option<(Ray, f64)> {
     let offset = self.sample_square(rng);
-    let pixel_sample = self.pixel00_loc
-      + ((i + offset.x) * self.pixel_delta_u)
-      + ((j + offset.y) * self.pixel_delta_v);
-    let ray_origin = if self.defocus_angle <= 0. {
-      self.center
-    } else {
-      self.defocus_disk_sample()
+    let (i, j) = (i + offset.x, j + offset.y);
+    let pixel_sample = self.pixel00_loc + (i * self.pixel_delta_u) + (j * self.pixel_delta_v);
+    let x = (i + 0.5) / self.width as f64 - 0.5;
+    let y = 0.5 - (j + 0.5) / self.height as f64;
+    let (eye_offset, convergence) = match self.stereo {
+      Some(stereo) => (0.5 * eye * stereo.interocular_distance(), stereo.convergence_distance()),
+      None => (0., f64::INFINITY),
     };
-    let ray_direction = pixel_sample - ray_origin;
-    Ray::new(ray_origin, ray_direction)
+    let mut offset = eye_offset * self.u;
+    let direction = match &self.projection {
+      Projection::Perspective => {
+        let ray_origin = self.lens_sample(self.center + offset);
+        let target = pixel_sample + (1. - self.focus_dist / convergence) * offset;
+        return Some((Ray::new(ray_origin, target - ray_origin), 1.));
+      }
+      Projection::Orthographic => {
+        let ray_origin = self.lens_sample(pixel_sample + self.focus_dist * self.w + offset);
+        return Some((Ray::new(ray_origin, pixel_sample + offset - ray_origin), 1.));
+      }
+      Projection::Equirectangular => {
+        let (phi, elevation) = (2. * PI * x, PI * y);
+        offset = eye_offset * elevation.cos() * (phi.cos() * self.u + phi.sin() * self.w);
+        elevation.cos() * (phi.sin() * self.u - phi.cos() * self.w) + elevation.sin() * self.v
+      }
+      Projection::Fisheye(mapping) => {
+        let (px, py) = (x * self.width as f64, y * self.height as f64);
+        let r = (px * px + py * py).sqrt() / (0.5 * self.width.min(self.height) as f64);
+        if r > 1. {
+          return None;
+        }
+        let fov = self.vfov.clamp(0., 360.).to_radians();
+        let theta = match mapping {
+          FisheyeMapping::Equidistant => 0.5 * r * fov,
+          FisheyeMapping::Equisolid => 2. * (r * (0.25 * fov).sin()).clamp(-1., 1.).asin(),
+        };
+        let phi = py.atan2(px);
+        theta.sin() * (phi.cos() * self.u + phi.sin() * self.v) - theta.cos() * self.w
+      }
+      Projection::Cylindrical => {
+        let h = 2. * (self.vfov.to_radians() / 2.).tan();
+        let angle = x * h * self.width as f64 / self.height as f64;
+        angle.sin() * self.u - angle.cos() * self.w + y * h * self.v
+      }
+      Projection::Realistic(lens) => {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let scale = lens.film_diagonal() / (width * width + height * height).sqrt();
+        let film = Vec3::new(-x * width * scale, -y * height * scale, 0.);
+        let (ray, weight) = lens.generate_ray(film, rng.random(), rng.random())?;
+        let to_world = |p: Vec3| p.x * self.u + p.y * self.v - p.z * self.w;
+        let ray_origin = self.center + offset + to_world(ray.origin);
+        return Some((Ray::new(ray_origin, to_world(ray.direction)), weight));
+      }
+    };
+    Some((Ray::new(self.center + offset, direction - offset / convergence), 1.))
   }
 
-  #[inline]
-  fn defocus_disk_sample(&self) -> Vec3 {
-    let p = Vec3::random_in_unit_disk();
-    self.center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v)
+  fn lens_sample(&self, center: Vec3) -> Vec3 {
+    if self.defocus_angle <= 0. {
+      center
+    } else {
+      let p = self.aperture.sample();
+      center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v)
+    }
   }
 
   #[inline]
-  fn sample_square(&self, rng: &mut ThreadRng) -> Vec3 {
+  fn sample_square(&self, rng: &mut LocalRng) -> Vec3 {
     Vec3::new(rng.random::<f64>() - 0.5, rng.random::<f64>() - 0.5, 0.)
   }
 
-  fn ray_color<T>(ray: Ray, depth: i32, world: &T) -> Color
+  fn ray_color<T>(
+    &self,
+    ray: Ray,
+    depth: i32,
+    world: &T,
+    lights: &LightList,
+    scattering_pdf: Option<f64>,
+  ) -> Color
   where
     T: Hittable,
   {
@@ -157,15 +375,258 @@ impl Camera {
       return Color::zero();
     }
     if let Some(record) = world.hit(ray, Interval::new(0.001, f64::INFINITY)) {
-      if let Some((scattered, attenuation)) = record.material.scatter(&ray, &record) {
-        attenuation * Self::ray_color(scattered, depth - 1, world)
-      } else {
+      let mut emitted = Self::path_spectrum(record.material.emitted(&ray, &record), &ray);
+      if let Some(pdf) = scattering_pdf
+        && !emitted.near_zero()
+      {
+        emitted *= power_heuristic(pdf, lights.pdf_hit(&ray, &record));
+      }
+      let direct = if record.material.is_specular(&record) {
         Color::zero()
+      } else {
+        Self::sample_lights(&ray, &record, world, lights)
+      };
+      if let Some(ScatterSample {
+        ray: mut scattered,
+        attenuation,
+        specular,
+      }) = record.material.sample(&ray, &record)
+      {
+        let scattering_pdf = if specular {
+          None
+        } else {
+          Some(record.material.pdf(&ray, &record, scattered.direction))
+        };
+        let attenuation = match ray.wavelengths {
+          Some(incoming) => {
+            let outgoing = *scattered.wavelengths.get_or_insert(incoming);
+            let attenuation = spectrum::upsample(attenuation, &outgoing);
+            if outgoing.secondary_terminated() && !incoming.secondary_terminated() {
+              Color::new(3. * attenuation.x, 0., 0.)
+            } else {
+              attenuation
+            }
+          }
+          None => attenuation,
+        };
+        emitted
+          + direct
+          + attenuation * self.ray_color(scattered, depth - 1, world, lights, scattering_pdf)
+      } else {
+        emitted + direct
       }
     } else {
       let unit_direction = ray.direction.normalization();
-      let t = 0.5 * (unit_direction.y + 1.0);
-      (1.0 - t) * Color::one() + t * Color::new(0.5, 0.7, 1.0)
+      let mut background_is_light = false;
+      let light = lights
+        .infinite(ray.origin)
+        .fold(Color::zero(), |radiance, (light, pmf)| {
+          background_is_light |= std::ptr::addr_eq(light, Arc::as_ptr(&self.background));
+          let weight = match scattering_pdf {
+            Some(pdf) => power_heuristic(pdf, pmf * light.pdf(ray.origin, unit_direction)),
+            None => 1.,
+          };
+          radiance + weight * light.emitted(&ray)
+        });
+      // A sky that is both the background and a light is only counted once, through the light.
+      let sky = if background_is_light {
+        Color::zero()
+      } else {
+        self.background.color(&ray)
+      };
+      Self::path_spectrum(sky + light, &ray)
     }
   }
+
+  fn sample_lights<T>(ray: &Ray, record: &HitRecord, world: &T, lights: &LightList) -> Color
+  where
+    T: Hittable,
+  {
+    let Some((light, sample)) = lights.sample(record.point) else {
+      return Color::zero();
+    };
+    if sample.pdf <= 0. {
+      return Color::zero();
+    }
+    let f = record.material.eval(ray, record, sample.direction);
+    if f.near_zero() {
+      return Color::zero();
+    }
+    let shadow = Ray::new(record.point, sample.direction);
+    let visibility =
+      world.transmittance(shadow, Interval::new(0.001, sample.distance * (1. - 1e-4)));
+    if visibility <= 0. {
+      return Color::zero();
+    }
+    let weight = if light.is_delta() {
+      1.
+    } else {
+      power_heuristic(sample.pdf, record.material.pdf(ray, record, sample.direction))
+    };
+    let contribution = visibility * weight / sample.pdf * f;
+    Self::path_spectrum(contribution, ray) * Self::path_spectrum(sample.radiance, ray)
+  }
+
+  fn path_spectrum(color: Color, ray: &Ray) -> Color {
+    match ray.wavelengths {
+      Some(wavelengths) => spectrum::upsample(color, &wavelengths),
+      None => color,
+    }
+  }
+}
+
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+  let (f, g) = (pdf * pdf, other_pdf * other_pdf);
+  if f + g > 0. { f / (f + g) } else { 0. }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::hittable_list::HittableList;
+  use crate::algorithm::material::Lambertian;
+  use crate::algorithm::quad::Quad;
+  use crate::algorithm::sky::PhysicalSky;
+  use crate::algorithm::sphere::Sphere;
+
+  fn scene() -> HittableList {
+    let mut world = HittableList::default();
+    let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Sphere::new(Vec3::new(0., 0., -1.), 0.5, material.clone()));
+    world.add(Sphere::new(Vec3::new(0., -100.5, -1.), 100., material));
+    world
+  }
+
+  fn camera(parallelism: Parallelism) -> Camera {
+    let mut camera =
+      Camera::new(16, 8, 4, 4, Vec3::zero(), Vec3::new(0., 0., -1.), Vec3::new(0., 1., 0.));
+    camera.parallelism = parallelism;
+    camera.seed = Some(7);
+    camera
+  }
+
+  #[test]
+  fn seeded_renders_match_across_parallelism() {
+    let world = scene();
+    let lights = LightList::default();
+    let serial = camera(Parallelism::Serial).render(&world, &lights);
+    let threads = camera(Parallelism::threads(3).unwrap()).render(&world, &lights);
+    let global = camera(Parallelism::Global).render(&world, &lights);
+    assert_eq!(serial.len(), 16 * 8 * 4);
+    assert_eq!(serial, threads);
+    assert_eq!(serial, global);
+  }
+
+  #[test]
+  fn unseeded_serial_render_draws_from_the_caller_rng() {
+    use rand::RngCore;
+
+    let world = scene();
+    let render = || {
+      random::seed(11);
+      let mut camera = camera(Parallelism::Serial);
+      camera.seed = None;
+      let image = camera.render(&world, &LightList::default());
+      (image, random::rng().next_u64())
+    };
+    assert_eq!(render(), render());
+  }
+
+  fn mean(image: &[u8]) -> f64 {
+    image.iter().map(|&byte| byte as f64).sum::<f64>() / image.len() as f64
+  }
+
+  #[test]
+  fn sky_used_as_background_and_light_is_counted_once() {
+    let mut world = HittableList::default();
+    world.add(Quad::new(
+      Vec3::new(-50., -0.5, 50.),
+      Vec3::new(100., 0., 0.),
+      Vec3::new(0., 0., -100.),
+      Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    ));
+    let sky = Arc::new(PhysicalSky::new(30., 0., 1.));
+    let mut camera =
+      Camera::new(16, 8, 256, 4, Vec3::zero(), Vec3::new(0., 0., -1.), Vec3::new(0., 1., 0.));
+    camera.seed = Some(7);
+    camera.background = sky.clone();
+    let reference = mean(&camera.render(&world, &LightList::default()));
+    let mut lights = LightList::default();
+    lights.add_shared(sky);
+    let both = mean(&camera.render(&world, &lights));
+    let mut lights = LightList::default();
+    lights.add(PhysicalSky::new(30., 0., 1.));
+    let separate = mean(&camera.render(&world, &lights));
+    assert!(reference > 0.);
+    assert!((both - reference).abs() < 0.02 * reference, "{both} {reference}");
+    assert!(separate > 1.1 * reference, "{separate} {reference}");
+  }
+
+  #[test]
+  fn stereo_rejects_non_positive_convergence() {
+    assert!(Stereo::new(StereoLayout::SideBySide, 0.064, 0.).is_none());
+    assert!(Stereo::new(StereoLayout::SideBySide, 0.064, -2.).is_none());
+    assert!(Stereo::new(StereoLayout::TopBottom, 0.064, f64::NAN).is_none());
+    assert!(Stereo::new(StereoLayout::TopBottom, f64::NAN, 2.).is_none());
+    let parallel = Stereo::new(StereoLayout::TopBottom, 0.064, f64::INFINITY);
+    assert!(parallel.is_some());
+
+    let mut camera = camera(Parallelism::Serial);
+    camera.stereo = parallel;
+    let image = camera.render(&scene(), &LightList::default());
+    assert_eq!(image.len(), 16 * 16 * 4);
+  }
+
+  fn projected(projection: Projection, vfov: f64, i: f64, j: f64) -> Vec3 {
+    let mut camera =
+      Camera::new(1001, 501, 1, 1, Vec3::zero(), Vec3::new(0., 0., -1.), Vec3::new(0., 1., 0.));
+    camera.projection = projection;
+    camera.vfov = vfov;
+    camera.initialize();
+    random::seed(5);
+    let mut rng = random::rng();
+    loop {
+      if let Some((ray, _)) = camera.ray(&mut rng, i, j, 0.) {
+        return ray.direction.normalization();
+      }
+    }
+  }
+
+  #[test]
+  fn equirectangular_centre_looks_down_the_view_axis() {
+    let direction = projected(Projection::Equirectangular, 90., 500., 250.);
+    assert!(direction.dot(Vec3::new(0., 0., -1.)) > (0.01f64).cos());
+  }
+
+  #[test]
+  fn fisheye_edge_reaches_the_field_of_view() {
+    for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+      for fov in [120., 180., 220.] {
+        let direction = projected(Projection::Fisheye(mapping), fov, 750., 250.);
+        let theta = direction.dot(Vec3::new(0., 0., -1.)).acos().to_degrees();
+        assert!((theta - 0.5 * fov).abs() < 1., "{mapping:?} {fov}: {theta}");
+      }
+    }
+  }
+
+  #[test]
+  fn realistic_projection_carries_its_lens() {
+    let source = "29.475 3.76 1.67 25.2\n84.83 0.12 1 25.2\n0 4.5 0 17.1\n-39.73 5 1 20\n";
+    let elements = RealisticLens::parse(source).unwrap();
+    let lens = Arc::new(RealisticLens::new(elements.clone(), 35., 2.).unwrap());
+    let other = Arc::new(RealisticLens::new(elements, 35., 2.).unwrap());
+    let projection = Projection::Realistic(lens.clone());
+    assert_eq!(projection, Projection::Realistic(lens));
+    assert_ne!(projection, Projection::Realistic(other));
+    assert_ne!(projection, Projection::Perspective);
+
+    let mut camera = camera(Parallelism::Serial);
+    camera.projection = projection;
+    let image = camera.render(&HittableList::default(), &LightList::default());
+    assert!(
+      image
+        .chunks(4)
+        .any(|pixel| pixel[..3].iter().any(|&byte| byte > 0))
+    );
+  }
 }
@@ -3,6 +3,10 @@ use super::vec3::Vec3;
 
 pub type Color = Vec3;
 
+pub fn luminance(color: Color) -> f64 {
+  0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
 pub fn linear_to_gamma(linear_component: f64) -> f64 {
   if linear_component > 0. {
     linear_component.sqrt()
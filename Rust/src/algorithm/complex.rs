@@ -0,0 +1,74 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Complex {
+  pub re: f64,
+  pub im: f64,
+}
+
+impl Complex {
+  pub fn new(re: f64, im: f64) -> Complex {
+    Complex { re, im }
+  }
+
+  pub fn norm(&self) -> f64 {
+    self.re * self.re + self.im * self.im
+  }
+
+  pub fn exp(self) -> Complex {
+    let magnitude = self.re.exp();
+    Complex::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+  }
+
+  pub fn sqrt(self) -> Complex {
+    let n = self.norm().sqrt();
+    if n == 0. {
+      return Complex::default();
+    }
+    let t1 = (0.5 * (n + self.re.abs())).sqrt();
+    let t2 = 0.5 * self.im / t1;
+    if self.re >= 0. {
+      Complex::new(t1, t2)
+    } else {
+      Complex::new(t2.abs(), t1.copysign(self.im))
+    }
+  }
+}
+
+impl From<f64> for Complex {
+  fn from(re: f64) -> Self {
+    Complex::new(re, 0.)
+  }
+}
+
+impl Add for Complex {
+  type Output = Self;
+  fn add(self, rhs: Self) -> Self::Output {
+    Complex::new(self.re + rhs.re, self.im + rhs.im)
+  }
+}
+
+impl Sub for Complex {
+  type Output = Self;
+  fn sub(self, rhs: Self) -> Self::Output {
+    Complex::new(self.re - rhs.re, self.im - rhs.im)
+  }
+}
+
+impl Mul for Complex {
+  type Output = Self;
+  fn mul(self, rhs: Self) -> Self::Output {
+    Complex::new(self.re * rhs.re - self.im * rhs.im, self.re * rhs.im + self.im * rhs.re)
+  }
+}
+
+impl Div for Complex {
+  type Output = Self;
+  fn div(self, rhs: Self) -> Self::Output {
+    let scale = 1. / rhs.norm();
+    Complex::new(
+      scale * (self.re * rhs.re + self.im * rhs.im),
+      scale * (self.im * rhs.re - self.re * rhs.im),
+    )
+  }
+}
@@ -0,0 +1,64 @@
+use std::f64::consts::PI;
+
+use super::aabb::Aabb;
+use super::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionCone {
+  pub w: Vec3,
+  pub cos_theta: f64,
+}
+
+impl DirectionCone {
+  pub fn new(w: Vec3, cos_theta: f64) -> DirectionCone {
+    DirectionCone {
+      w: w.normalization(),
+      cos_theta,
+    }
+  }
+
+  pub fn entire_sphere() -> DirectionCone {
+    DirectionCone {
+      w: Vec3::new(0., 0., 1.),
+      cos_theta: -1.,
+    }
+  }
+
+  pub fn bound_subtended_directions(bounds: &Aabb, point: Vec3) -> DirectionCone {
+    let (center, radius) = bounds.bounding_sphere();
+    let distance_squared = (point - center).len_squared();
+    if distance_squared < radius * radius {
+      return Self::entire_sphere();
+    }
+    let sin2_theta_max = radius * radius / distance_squared;
+    let cos_theta_max = (1. - sin2_theta_max).max(0.).sqrt();
+    DirectionCone::new(center - point, cos_theta_max)
+  }
+
+  pub fn union(&self, other: &DirectionCone) -> DirectionCone {
+    let theta_a = self.cos_theta.clamp(-1., 1.).acos();
+    let theta_b = other.cos_theta.clamp(-1., 1.).acos();
+    let theta_d = self.w.dot(other.w).clamp(-1., 1.).acos();
+    if (theta_d + theta_b).min(PI) <= theta_a {
+      return *self;
+    }
+    if (theta_d + theta_a).min(PI) <= theta_b {
+      return *other;
+    }
+
+    let theta_o = 0.5 * (theta_a + theta_d + theta_b);
+    if theta_o >= PI {
+      return Self::entire_sphere();
+    }
+    let theta_r = theta_o - theta_a;
+    let axis = Vec3::cross(self.w, other.w);
+    if axis.len_squared() == 0. {
+      return Self::entire_sphere();
+    }
+    let axis = axis.normalization();
+    let w = self.w * theta_r.cos()
+      + Vec3::cross(axis, self.w) * theta_r.sin()
+      + axis * axis.dot(self.w) * (1. - theta_r.cos());
+    DirectionCone::new(w, theta_o.cos())
+  }
+}
@@ -0,0 +1,111 @@
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+  func: Vec<f64>,
+  cdf: Vec<f64>,
+  integral: f64,
+}
+
+impl Distribution1D {
+  pub fn new(func: &[f64]) -> Distribution1D {
+    let n = func.len().max(1);
+    let func: Vec<f64> = if func.is_empty() {
+      vec![0.]
+    } else {
+      func.iter().map(|f| f.abs()).collect()
+    };
+    let mut cdf = vec![0.; n + 1];
+    for i in 1..=n {
+      cdf[i] = cdf[i - 1] + func[i - 1] / n as f64;
+    }
+    let integral = cdf[n];
+    for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+      *c = if integral > 0. {
+        *c / integral
+      } else {
+        i as f64 / n as f64
+      };
+    }
+    Distribution1D {
+      func,
+      cdf,
+      integral,
+    }
+  }
+
+  pub fn count(&self) -> usize {
+    self.func.len()
+  }
+
+  pub fn integral(&self) -> f64 {
+    self.integral
+  }
+
+  pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize) {
+    let offset = self
+      .cdf
+      .partition_point(|&c| c <= u)
+      .saturating_sub(1)
+      .min(self.count() - 1);
+    let width = self.cdf[offset + 1] - self.cdf[offset];
+    let du = if width > 0. {
+      (u - self.cdf[offset]) / width
+    } else {
+      0.
+    };
+    let x = ((offset as f64 + du) / self.count() as f64).min(1. - f64::EPSILON);
+    (x, self.pdf_at(offset), offset)
+  }
+
+  pub fn pdf(&self, x: f64) -> f64 {
+    let offset = ((x * self.count() as f64) as usize).min(self.count() - 1);
+    self.pdf_at(offset)
+  }
+
+  fn pdf_at(&self, offset: usize) -> f64 {
+    if self.integral > 0. {
+      self.func[offset] / self.integral
+    } else {
+      1.
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+  conditional: Vec<Distribution1D>,
+  marginal: Distribution1D,
+}
+
+impl Distribution2D {
+  pub fn new(func: &[f64], width: usize, height: usize) -> Distribution2D {
+    let conditional: Vec<Distribution1D> = (0..height)
+      .map(|j| Distribution1D::new(&func[j * width..(j + 1) * width]))
+      .collect();
+    let marginal = Distribution1D::new(
+      &conditional
+        .iter()
+        .map(|row| row.integral())
+        .collect::<Vec<_>>(),
+    );
+    Distribution2D {
+      conditional,
+      marginal,
+    }
+  }
+
+  pub fn integral(&self) -> f64 {
+    self.marginal.integral()
+  }
+
+  pub fn sample(&self, u: f64, v: f64) -> (f64, f64, f64) {
+    let (y, pdf_y, row) = self.marginal.sample_continuous(v);
+    let (x, pdf_x, _) = self.conditional[row].sample_continuous(u);
+    (x, y, pdf_x * pdf_y)
+  }
+
+  pub fn pdf(&self, x: f64, y: f64) -> f64 {
+    let height = self.conditional.len();
+    let row = ((y * height as f64) as usize).min(height - 1);
+    self.marginal.pdf(y) * self.conditional[row].pdf(x)
+  }
+}
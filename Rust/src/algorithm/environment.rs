@@ -0,0 +1,343 @@
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand::Rng;
+
+use super::background::Background;
+use super::color::{Color, luminance};
+use super::distribution::Distribution2D;
+use super::light::{Light, LightSample};
+use super::random;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+const MAX_DIMENSION: usize = 1 << 15;
+
+pub struct EnvironmentMap {
+  pub scale: f64,
+  width: usize,
+  height: usize,
+  pixels: Vec<Color>,
+  distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+  pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> EnvironmentMap {
+    let (width, height) = (width.max(1), height.max(1));
+    let mut pixels = pixels;
+    pixels.resize(width * height, Color::zero());
+    let weights: Vec<f64> = (0..height)
+      .flat_map(|j| {
+        let sin_theta = (PI * (j as f64 + 0.5) / height as f64).sin();
+        let pixels = &pixels;
+        (0..width).map(move |i| luminance(pixels[j * width + i]).max(0.) * sin_theta)
+      })
+      .collect();
+    EnvironmentMap {
+      scale: 1.,
+      width,
+      height,
+      distribution: Distribution2D::new(&weights, width, height),
+      pixels,
+    }
+  }
+
+  pub fn from_background(
+    background: &dyn Background,
+    width: usize,
+    height: usize,
+  ) -> EnvironmentMap {
+    let pixels = (0..height)
+      .flat_map(|j| {
+        (0..width).map(move |i| {
+          let u = (i as f64 + 0.5) / width as f64;
+          let v = (j as f64 + 0.5) / height as f64;
+          background.color(&Ray::new(Vec3::zero(), Self::direction(u, v)))
+        })
+      })
+      .collect();
+    Self::new(width, height, pixels)
+  }
+
+  pub fn load_hdr<P: AsRef<Path>>(path: P) -> io::Result<EnvironmentMap> {
+    Self::parse_hdr(&fs::read(path)?)
+  }
+
+  pub fn parse_hdr(bytes: &[u8]) -> io::Result<EnvironmentMap> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut lines = bytes.split(|&b| b == b'\n');
+    let mut offset = 0;
+    let mut next_line = || {
+      let line = lines.next().ok_or_else(|| invalid("truncated header"))?;
+      offset += line.len() + 1;
+      Ok::<_, io::Error>(String::from_utf8_lossy(line).trim().to_string())
+    };
+
+    if !next_line()?.starts_with("#?") {
+      return Err(invalid("missing Radiance signature"));
+    }
+    loop {
+      let line = next_line()?;
+      if line.is_empty() {
+        break;
+      }
+      if let Some(format) = line.strip_prefix("FORMAT=")
+        && format != "32-bit_rle_rgbe"
+      {
+        return Err(invalid("only 32-bit_rle_rgbe is supported"));
+      }
+    }
+    let resolution = next_line()?;
+    let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+      ["-Y", height, "+X", width] => (
+        height
+          .parse::<usize>()
+          .map_err(|_| invalid("malformed height"))?,
+        width
+          .parse::<usize>()
+          .map_err(|_| invalid("malformed width"))?,
+      ),
+      _ => return Err(invalid("unsupported image orientation")),
+    };
+    if !(1..=MAX_DIMENSION).contains(&width) || !(1..=MAX_DIMENSION).contains(&height) {
+      return Err(invalid("image dimensions out of range"));
+    }
+    let count = width
+      .checked_mul(height)
+      .ok_or_else(|| invalid("image dimensions overflow"))?;
+
+    let data = bytes.get(offset..).unwrap_or_default();
+    if data.len() < height.saturating_mul(4) {
+      return Err(invalid("truncated pixel data"));
+    }
+    let mut pixels = Vec::with_capacity(count.min(data.len()));
+    let mut data = data.iter().copied();
+    let mut next = || data.next().ok_or_else(|| invalid("truncated pixel data"));
+    let mut scanline = vec![[0u8; 4]; width];
+    for _ in 0..height {
+      let head = [next()?, next()?, next()?, next()?];
+      let encoded = (8..0x8000).contains(&width)
+        && head[0] == 2
+        && head[1] == 2
+        && ((head[2] as usize) << 8 | head[3] as usize) == width;
+      if encoded {
+        for channel in 0..4 {
+          let mut x = 0;
+          while x < width {
+            let count = next()? as usize;
+            if count > 128 {
+              let run = count - 128;
+              if x + run > width {
+                return Err(invalid("run exceeds scanline"));
+              }
+              let value = next()?;
+              for pixel in &mut scanline[x..x + run] {
+                pixel[channel] = value;
+              }
+              x += run;
+            } else {
+              if count == 0 || x + count > width {
+                return Err(invalid("bad scanline length"));
+              }
+              for pixel in &mut scanline[x..x + count] {
+                pixel[channel] = next()?;
+              }
+              x += count;
+            }
+          }
+        }
+      } else {
+        scanline[0] = head;
+        for pixel in scanline.iter_mut().skip(1) {
+          *pixel = [next()?, next()?, next()?, next()?];
+        }
+      }
+      pixels.extend(scanline.iter().map(|&rgbe| rgbe_to_color(rgbe)));
+    }
+    Ok(Self::new(width, height, pixels))
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn radiance(&self, direction: Vec3) -> Color {
+    let (u, v) = Self::uv(direction);
+    let i = ((u * self.width as f64) as usize).min(self.width - 1);
+    let j = ((v * self.height as f64) as usize).min(self.height - 1);
+    self.scale * self.pixels[j * self.width + i]
+  }
+
+  fn uv(direction: Vec3) -> (f64, f64) {
+    let direction = direction.normalization();
+    let phi = direction.x.atan2(direction.z).rem_euclid(2. * PI);
+    let theta = direction.y.clamp(-1., 1.).acos();
+    (phi / (2. * PI), theta / PI)
+  }
+
+  fn direction(u: f64, v: f64) -> Vec3 {
+    let (phi, theta) = (2. * PI * u, PI * v);
+    Vec3::new(theta.sin() * phi.sin(), theta.cos(), theta.sin() * phi.cos())
+  }
+}
+
+fn rgbe_to_color([r, g, b, e]: [u8; 4]) -> Color {
+  if e == 0 {
+    return Color::zero();
+  }
+  let f = 2f64.powi(e as i32 - 136);
+  Color::new(r as f64 * f, g as f64 * f, b as f64 * f)
+}
+
+impl Background for EnvironmentMap {
+  fn color(&self, ray: &Ray) -> Color {
+    self.radiance(ray.direction)
+  }
+}
+
+impl Light for EnvironmentMap {
+  fn sample(&self, _point: Vec3) -> Option<LightSample> {
+    let mut rng = random::rng();
+    let (u, v, map_pdf) = self.distribution.sample(rng.random(), rng.random());
+    let sin_theta = (PI * v).sin();
+    if map_pdf <= 0. || sin_theta <= 0. {
+      return None;
+    }
+    let direction = Self::direction(u, v);
+    Some(LightSample {
+      direction,
+      distance: f64::INFINITY,
+      radiance: self.radiance(direction),
+      pdf: map_pdf / (2. * PI * PI * sin_theta),
+    })
+  }
+
+  fn pdf(&self, _point: Vec3, direction: Vec3) -> f64 {
+    let (u, v) = Self::uv(direction);
+    let sin_theta = (PI * v).sin();
+    if sin_theta <= 0. {
+      return 0.;
+    }
+    self.distribution.pdf(u, v) / (2. * PI * PI * sin_theta)
+  }
+
+  fn emitted(&self, ray: &Ray) -> Color {
+    self.radiance(ray.direction)
+  }
+
+  fn is_delta(&self) -> bool {
+    false
+  }
+
+  fn power(&self) -> f64 {
+    PI * self.scale * PI * self.distribution.integral() / 2.
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::camera::Camera;
+  use crate::algorithm::hittable_list::HittableList;
+  use crate::algorithm::light::LightList;
+  use crate::algorithm::material::Lambertian;
+  use crate::algorithm::quad::Quad;
+  use std::sync::Arc;
+
+  fn gradient_map() -> EnvironmentMap {
+    let (width, height) = (16, 8);
+    let pixels = (0..width * height)
+      .map(|index| {
+        let (i, j) = (index % width, index / width);
+        Color::new(1. + i as f64, 0.5 + (j * j) as f64, 0.25)
+      })
+      .collect();
+    EnvironmentMap::new(width, height, pixels)
+  }
+
+  fn hdr(resolution: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytes = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{resolution}\n").into_bytes();
+    bytes.extend_from_slice(data);
+    bytes
+  }
+
+  #[test]
+  fn light_pdf_integrates_to_one() {
+    let map = gradient_map();
+    let (steps_u, steps_v) = (256, 256);
+    let mut integral = 0.;
+    for j in 0..steps_v {
+      for i in 0..steps_u {
+        let (u, v) = ((i as f64 + 0.5) / steps_u as f64, (j as f64 + 0.5) / steps_v as f64);
+        let sin_theta = (PI * v).sin();
+        let area = 2. * PI * PI * sin_theta / (steps_u * steps_v) as f64;
+        integral += map.pdf(Vec3::zero(), EnvironmentMap::direction(u, v)) * area;
+      }
+    }
+    assert!((integral - 1.).abs() < 1e-3);
+  }
+
+  #[test]
+  fn parses_flat_scanlines() {
+    let map =
+      EnvironmentMap::parse_hdr(&hdr("-Y 1 +X 2", &[128, 64, 32, 129, 0, 0, 0, 0])).unwrap();
+    assert_eq!((map.width(), map.height()), (2, 1));
+    let color = map.pixels[0];
+    assert_eq!((color.x, color.y, color.z), (1., 0.5, 0.25));
+  }
+
+  #[test]
+  fn rejects_malformed_images() {
+    let invalid = |bytes: &[u8]| {
+      EnvironmentMap::parse_hdr(bytes)
+        .err()
+        .map(|error| error.kind())
+    };
+    let invalid_data = Some(io::ErrorKind::InvalidData);
+    assert_eq!(invalid(b"P6\n"), invalid_data);
+    assert_eq!(invalid(&hdr("-Y 0 +X 4", &[])), invalid_data);
+    assert_eq!(invalid(&hdr("-Y 100000000000 +X 100000000000", &[])), invalid_data);
+    assert_eq!(invalid(&hdr("-Y 4 +X 4", &[1, 2, 3])), invalid_data);
+    assert_eq!(invalid(&hdr("+X 4 -Y 4", &[0; 64])), invalid_data);
+    assert_eq!(invalid(&hdr("-Y 1 +X 8", &[2, 2, 0, 8, 200])), invalid_data);
+  }
+
+  #[test]
+  fn map_used_as_background_and_light_is_counted_once() {
+    let mut world = HittableList::default();
+    world.add(Quad::new(
+      Vec3::new(-50., -0.5, 50.),
+      Vec3::new(100., 0., 0.),
+      Vec3::new(0., 0., -100.),
+      Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    ));
+    let map = || {
+      let mut map = gradient_map();
+      map.scale = 0.02;
+      map
+    };
+    let mean =
+      |image: Vec<u8>| image.iter().map(|&byte| byte as f64).sum::<f64>() / image.len() as f64;
+    let background = Arc::new(map());
+    let mut camera =
+      Camera::new(16, 8, 256, 4, Vec3::zero(), Vec3::new(0., 0., -1.), Vec3::new(0., 1., 0.));
+    camera.seed = Some(3);
+    camera.background = background.clone();
+    let reference = mean(camera.render(&world, &LightList::default()));
+    let mut lights = LightList::default();
+    lights.add_shared(background);
+    let both = mean(camera.render(&world, &lights));
+    let mut lights = LightList::default();
+    lights.add(map());
+    let separate = mean(camera.render(&world, &lights));
+    assert!(reference > 0.);
+    assert!((both - reference).abs() < 0.02 * reference, "{both} {reference}");
+    assert!(separate > 1.1 * reference, "{separate} {reference}");
+  }
+}
@@ -1,18 +1,50 @@
+use std::sync::Arc;
+
 use rand::Rng;
 
-use super::camera::Camera;
+use super::camera::{Camera, Parallelism};
 use super::color::Color;
 use super::constant::{MAX_DEPTH, SAMPLES_PER_PIXEL};
 use super::hittable_list::HittableList;
-use super::material::{Dielectric, Lambertian, Metal};
+use super::light::LightList;
+use super::material::{Dielectric, Lambertian, MaterialLibrary, Metal};
+use super::random;
 use super::sphere::Sphere;
 use super::vec3::Vec3;
 
+const SERIAL_SEED: u64 = 0;
+
 pub fn generate_raw_data(width: usize, height: usize) -> Vec<u8> {
-  let mut rng = rand::rng();
+  generate(width, height, Parallelism::Global)
+}
+
+pub fn generate_raw_data_with_threads(width: usize, height: usize, threads: usize) -> Vec<u8> {
+  let parallelism = match threads {
+    0 => Parallelism::Global,
+    1 => Parallelism::Serial,
+    n => Parallelism::threads(n).unwrap_or_else(|error| {
+      eprintln!("failed to build a {n}-thread pool, rendering on the global pool: {error}");
+      Parallelism::Global
+    }),
+  };
+  generate(width, height, parallelism)
+}
+
+fn generate(width: usize, height: usize, parallelism: Parallelism) -> Vec<u8> {
+  let seed = matches!(parallelism, Parallelism::Serial).then_some(SERIAL_SEED);
+  if let Some(seed) = seed {
+    random::seed(seed);
+  }
+  let mut rng = random::rng();
   let mut world = HittableList::default();
-  let material_ground = Lambertian::new(Color::new(0.5, 0.5, 0.5));
-  world.add(Box::new(Sphere::new(Vec3::new(0., -1000., 0.), 1000., material_ground)));
+  let mut materials = MaterialLibrary::new();
+  let material_ground = materials
+    .add("ground", Lambertian::new(Color::new(0.5, 0.5, 0.5)))
+    .expect("scene material names are unique");
+  let glass = materials
+    .add("glass", Dielectric::new(1.5))
+    .expect("scene material names are unique");
+  world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., material_ground));
 
   for a in -11..11 {
     for b in -11..11 {
@@ -23,29 +55,27 @@ pub fn generate_raw_data(width: usize, height: usize) -> Vec<u8> {
       if (center - Vec3::new(4., 0.2, 0.)).len() > 0.9 {
         if choose_mat < 0.8 {
           let albedo = Color::random() * Color::random();
-          let sphere_material = Lambertian::new(albedo);
-          world.add(Box::new(Sphere::new(center, 0.2, sphere_material)));
+          let sphere_material = Arc::new(Lambertian::new(albedo));
+          world.add(Sphere::new(center, 0.2, sphere_material));
         } else if choose_mat < 0.95 {
           let albedo = Color::random_range(0.5, 1.);
           let fuzz = rng.random_range(0f64..0.5);
-          let sphere_material = Metal::new(albedo, fuzz);
-          world.add(Box::new(Sphere::new(center, 0.2, sphere_material)));
+          let sphere_material = Arc::new(Metal::new(albedo, fuzz));
+          world.add(Sphere::new(center, 0.2, sphere_material));
         } else {
-          let sphere_material = Dielectric::new(1.5);
-          world.add(Box::new(Sphere::new(center, 0.2, sphere_material)));
+          world.add(Sphere::new(center, 0.2, glass.clone()));
         }
       }
     }
   }
 
-  let material1 = Dielectric::new(1.5);
-  world.add(Box::new(Sphere::new(Vec3::new(0., 1., 0.), 1., material1)));
+  world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., glass));
 
-  let material2 = Lambertian::new(Color::new(0.4, 0.2, 0.1));
-  world.add(Box::new(Sphere::new(Vec3::new(-4., 1., 0.), 1., material2)));
+  let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+  world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., material2));
 
-  let material3 = Metal::new(Color::new(0.7, 0.6, 0.5), 0.);
-  world.add(Box::new(Sphere::new(Vec3::new(4., 1., 0.), 1., material3)));
+  let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.));
+  world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., material3));
 
   let mut camera = Camera::new(
     width,
@@ -59,5 +89,20 @@ pub fn generate_raw_data(width: usize, height: usize) -> Vec<u8> {
   camera.vfov = 20.;
   camera.defocus_angle = 0.6;
   camera.focus_dist = 10.;
-  camera.render(&world)
+  camera.parallelism = parallelism;
+  camera.seed = seed;
+  camera.render(&world, &LightList::default())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn serial_render_is_deterministic() {
+    let first = generate_raw_data_with_threads(8, 4, 1);
+    let second = generate_raw_data_with_threads(8, 4, 1);
+    assert_eq!(first.len(), 8 * 4 * 4);
+    assert_eq!(first, second);
+  }
 }
@@ -1,5 +1,11 @@
+use std::sync::Arc;
+
+use super::aabb::Aabb;
+use super::color::Color;
+use super::direction_cone::DirectionCone;
 use super::interval::Interval;
 use super::material::Material;
+use super::onb::Onb;
 use super::ray::Ray;
 use super::vec3::Vec3;
 
@@ -7,7 +13,11 @@ use super::vec3::Vec3;
 pub struct HitRecord<'a> {
   pub point: Vec3,
   pub normal: Vec3,
+  pub tangent: Vec3,
+  pub bitangent: Vec3,
   pub t: f64,
+  pub u: f64,
+  pub v: f64,
   pub front_face: bool,
   pub material: &'a dyn Material,
 }
@@ -21,17 +31,186 @@ impl<'a> HitRecord<'a> {
     material: &'a dyn Material,
   ) -> HitRecord<'a> {
     let front_face = ray.direction.dot(normal) < 0.;
+    let frame = Onb::new(normal);
     let normal = if front_face { normal } else { -normal };
     HitRecord {
       point,
       normal,
+      tangent: frame.u,
+      bitangent: frame.v,
       t,
+      u: 0.,
+      v: 0.,
       material,
       front_face,
     }
   }
+
+  pub fn set_tangent(&mut self, tangent: Vec3) {
+    let outward = if self.front_face {
+      self.normal
+    } else {
+      -self.normal
+    };
+    let tangent = tangent - self.normal.dot(tangent) * self.normal;
+    if tangent.near_zero() {
+      let frame = Onb::new(outward);
+      self.tangent = frame.u;
+      self.bitangent = frame.v;
+      return;
+    }
+    self.tangent = tangent.normalization();
+    self.bitangent = Vec3::cross(outward, self.tangent);
+  }
+
+  pub fn set_shading_normal(&mut self, normal: Vec3) {
+    self.normal = normal.normalization();
+    self.set_tangent(self.tangent);
+  }
+
+  pub fn frame(&self) -> Onb {
+    Onb {
+      u: self.tangent,
+      v: self.bitangent,
+      w: self.normal,
+    }
+  }
+}
+
+pub trait Hittable: Send + Sync {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>>;
+
+  fn transmittance(&self, ray: Ray, interval: Interval) -> f64 {
+    if self.hit(ray, interval).is_some() {
+      0.
+    } else {
+      1.
+    }
+  }
+
+  fn pdf_value(&self, _origin: Vec3, _direction: Vec3) -> f64 {
+    0.
+  }
+
+  fn random(&self, _origin: Vec3) -> Vec3 {
+    Vec3::new(1., 0., 0.)
+  }
+
+  fn area(&self) -> f64 {
+    0.
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    None
+  }
+
+  fn normal_bounds(&self) -> DirectionCone {
+    DirectionCone::entire_sphere()
+  }
+
+  fn emission(&self) -> Color {
+    Color::zero()
+  }
+}
+
+impl<H> Hittable for Box<H>
+where
+  H: Hittable + ?Sized,
+{
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    (**self).hit(ray, interval)
+  }
+
+  fn transmittance(&self, ray: Ray, interval: Interval) -> f64 {
+    (**self).transmittance(ray, interval)
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    (**self).pdf_value(origin, direction)
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    (**self).random(origin)
+  }
+
+  fn area(&self) -> f64 {
+    (**self).area()
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    (**self).bounding_box()
+  }
+
+  fn normal_bounds(&self) -> DirectionCone {
+    (**self).normal_bounds()
+  }
+
+  fn emission(&self) -> Color {
+    (**self).emission()
+  }
 }
 
-pub trait Hittable {
-  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord>;
+impl<H> Hittable for Arc<H>
+where
+  H: Hittable + ?Sized,
+{
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    (**self).hit(ray, interval)
+  }
+
+  fn transmittance(&self, ray: Ray, interval: Interval) -> f64 {
+    (**self).transmittance(ray, interval)
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    (**self).pdf_value(origin, direction)
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    (**self).random(origin)
+  }
+
+  fn area(&self) -> f64 {
+    (**self).area()
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    (**self).bounding_box()
+  }
+
+  fn normal_bounds(&self) -> DirectionCone {
+    (**self).normal_bounds()
+  }
+
+  fn emission(&self) -> Color {
+    (**self).emission()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::material::Lambertian;
+
+  fn assert_orthonormal(record: &HitRecord) {
+    let frame = record.frame();
+    for (a, b) in [(frame.u, frame.v), (frame.v, frame.w), (frame.w, frame.u)] {
+      assert!(a.dot(b).abs() < 1e-9);
+    }
+    for axis in [frame.u, frame.v, frame.w] {
+      assert!((axis.len() - 1.).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn shading_normal_along_tangent_rebuilds_frame() {
+    let material = Lambertian::default();
+    let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+    let mut record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &material);
+    record.set_tangent(Vec3::new(1., 0., 0.));
+    record.set_shading_normal(Vec3::new(1., 0., 0.));
+    assert_orthonormal(&record);
+    record.set_tangent(Vec3::new(2., 0., 0.));
+    assert_orthonormal(&record);
+  }
 }
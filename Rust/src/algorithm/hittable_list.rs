@@ -1,10 +1,18 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::aabb::Aabb;
+use super::color::Color;
 use super::hittable::{HitRecord, Hittable};
 use super::interval::Interval;
+use super::random;
 use super::ray::Ray;
+use super::vec3::Vec3;
 
 #[derive(Default)]
 pub struct HittableList {
-  pub objects: Vec<Box<dyn Hittable>>,
+  pub objects: Vec<Arc<dyn Hittable>>,
 }
 
 impl HittableList {
@@ -14,7 +22,14 @@ impl HittableList {
     }
   }
 
-  pub fn add(&mut self, object: Box<dyn Hittable>) {
+  pub fn add<H>(&mut self, object: H)
+  where
+    H: Hittable + 'static,
+  {
+    self.objects.push(Arc::new(object));
+  }
+
+  pub fn add_shared(&mut self, object: Arc<dyn Hittable>) {
     self.objects.push(object);
   }
 
@@ -24,7 +39,7 @@ impl HittableList {
 }
 
 impl Hittable for HittableList {
-  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
     let mut ret = None;
     let mut close_so_far = interval.max;
     for obj in self.objects.iter() {
@@ -35,6 +50,58 @@ impl Hittable for HittableList {
     }
     ret
   }
-}
 
-unsafe impl Sync for HittableList {}
+  fn transmittance(&self, ray: Ray, interval: Interval) -> f64 {
+    let mut transmittance = 1.;
+    for obj in self.objects.iter() {
+      transmittance *= obj.transmittance(ray, Interval::new(interval.min, interval.max));
+      if transmittance <= 0. {
+        return 0.;
+      }
+    }
+    transmittance
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    if self.objects.is_empty() {
+      return 0.;
+    }
+    let weight = 1. / self.objects.len() as f64;
+    self
+      .objects
+      .iter()
+      .map(|object| weight * object.pdf_value(origin, direction))
+      .sum()
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    if self.objects.is_empty() {
+      return Vec3::new(1., 0., 0.);
+    }
+    let index = random::rng().random_range(0..self.objects.len());
+    self.objects[index].random(origin)
+  }
+
+  fn area(&self) -> f64 {
+    self.objects.iter().map(|object| object.area()).sum()
+  }
+
+  fn emission(&self) -> Color {
+    let area = self.area();
+    if area <= 0. {
+      return Color::zero();
+    }
+    self
+      .objects
+      .iter()
+      .map(|object| object.area() / area * object.emission())
+      .fold(Color::zero(), |total, emission| total + emission)
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    self
+      .objects
+      .iter()
+      .try_fold(Aabb::empty(), |bounds, object| Some(bounds.union(&object.bounding_box()?)))
+  }
+}
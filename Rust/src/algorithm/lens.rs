@@ -0,0 +1,403 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::aabb::Aabb;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+const EXIT_PUPIL_BOUNDS: usize = 64;
+const EXIT_PUPIL_SAMPLES: usize = 16384;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LensElement {
+  pub curvature_radius: f64,
+  pub thickness: f64,
+  pub eta: f64,
+  pub aperture_radius: f64,
+}
+
+impl LensElement {
+  pub fn new(curvature_radius: f64, thickness: f64, eta: f64, aperture_radius: f64) -> LensElement {
+    LensElement {
+      curvature_radius,
+      thickness,
+      eta,
+      aperture_radius,
+    }
+  }
+
+  fn is_stop(&self) -> bool {
+    self.curvature_radius == 0.
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RealisticLens {
+  elements: Vec<LensElement>,
+  film_diagonal: f64,
+  exit_pupil_bounds: Vec<Aabb>,
+}
+
+impl RealisticLens {
+  pub fn new(
+    elements: Vec<LensElement>,
+    film_diagonal: f64,
+    focus_distance: f64,
+  ) -> io::Result<RealisticLens> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+    if elements.is_empty() {
+      return Err(invalid("lens has no elements"));
+    }
+    if !(film_diagonal > 0. && film_diagonal.is_finite()) {
+      return Err(invalid("film diagonal must be positive"));
+    }
+    let mut lens = RealisticLens {
+      elements,
+      film_diagonal: film_diagonal * 0.001,
+      exit_pupil_bounds: Vec::new(),
+    };
+    let thickness = lens
+      .focus_thick_lens(focus_distance)
+      .ok_or_else(|| invalid("lens cannot focus at the requested distance"))?;
+    if let Some(rear) = lens.elements.last_mut() {
+      rear.thickness = thickness;
+    }
+    lens.exit_pupil_bounds = (0..EXIT_PUPIL_BOUNDS)
+      .map(|i| {
+        let radius = 0.5 * lens.film_diagonal;
+        let r0 = i as f64 / EXIT_PUPIL_BOUNDS as f64 * radius;
+        let r1 = (i + 1) as f64 / EXIT_PUPIL_BOUNDS as f64 * radius;
+        lens.bound_exit_pupil(r0, r1)
+      })
+      .collect();
+    Ok(lens)
+  }
+
+  pub fn load<P: AsRef<Path>>(
+    path: P,
+    film_diagonal: f64,
+    focus_distance: f64,
+  ) -> io::Result<RealisticLens> {
+    let elements = Self::parse(&fs::read_to_string(path)?)?;
+    Self::new(elements, film_diagonal, focus_distance)
+  }
+
+  pub fn parse(source: &str) -> io::Result<Vec<LensElement>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let elements = source
+      .lines()
+      .map(|line| line.split('#').next().unwrap_or_default().trim())
+      .filter(|line| !line.is_empty())
+      .map(|line| {
+        let values = line
+          .split_whitespace()
+          .map(|value| value.trim_matches(|c| c == '(' || c == ')').parse::<f64>())
+          .collect::<Result<Vec<_>, _>>()
+          .map_err(|_| invalid("malformed number in lens prescription"))?;
+        match values[..] {
+          [radius, thickness, eta, aperture] => {
+            Ok(LensElement::new(radius * 0.001, thickness * 0.001, eta, aperture * 0.0005))
+          }
+          _ => Err(invalid("lens elements need radius, thickness, ior and aperture")),
+        }
+      })
+      .collect::<io::Result<Vec<_>>>()?;
+    if elements.is_empty() {
+      return Err(invalid("lens prescription has no elements"));
+    }
+    Ok(elements)
+  }
+
+  pub fn elements(&self) -> &[LensElement] {
+    &self.elements
+  }
+
+  pub fn film_diagonal(&self) -> f64 {
+    self.film_diagonal
+  }
+
+  pub fn generate_ray(&self, film: Vec3, u: f64, v: f64) -> Option<(Ray, f64)> {
+    let (rear, area) = self.sample_exit_pupil(film, u, v);
+    let direction = (rear - film).normalization();
+    let ray = self.trace_from_film(Ray::new(film, direction))?;
+    let center = self.exit_pupil_bounds[0].size();
+    let weight = direction.z.powi(4) * area / (center.x * center.y);
+    Some((ray, weight))
+  }
+
+  fn rear_z(&self) -> f64 {
+    self.elements.last().map_or(0., |element| element.thickness)
+  }
+
+  fn front_z(&self) -> f64 {
+    self.elements.iter().map(|element| element.thickness).sum()
+  }
+
+  fn rear_radius(&self) -> f64 {
+    self
+      .elements
+      .last()
+      .map_or(0., |element| element.aperture_radius)
+  }
+
+  fn trace_from_film(&self, ray: Ray) -> Option<Ray> {
+    let mut origin = Vec3::new(ray.origin.x, ray.origin.y, -ray.origin.z);
+    let mut direction = Vec3::new(ray.direction.x, ray.direction.y, -ray.direction.z);
+    let mut element_z = 0.;
+    for (i, element) in self.elements.iter().enumerate().rev() {
+      element_z -= element.thickness;
+      let eta_t = match i.checked_sub(1).map(|j| self.elements[j].eta) {
+        Some(eta) if eta != 0. => eta,
+        _ => 1.,
+      };
+      (origin, direction) =
+        Self::interface(element, element_z, origin, direction, element.eta, eta_t)?;
+    }
+    Some(Ray::new(
+      Vec3::new(origin.x, origin.y, -origin.z),
+      Vec3::new(direction.x, direction.y, -direction.z),
+    ))
+  }
+
+  fn trace_from_scene(&self, ray: Ray) -> Option<Ray> {
+    let mut origin = Vec3::new(ray.origin.x, ray.origin.y, -ray.origin.z);
+    let mut direction = Vec3::new(ray.direction.x, ray.direction.y, -ray.direction.z);
+    let mut element_z = -self.front_z();
+    for (i, element) in self.elements.iter().enumerate() {
+      let eta_i = match i.checked_sub(1).map(|j| self.elements[j].eta) {
+        Some(eta) if eta != 0. => eta,
+        _ => 1.,
+      };
+      let eta_t = if element.eta != 0. { element.eta } else { 1. };
+      (origin, direction) = Self::interface(element, element_z, origin, direction, eta_i, eta_t)?;
+      element_z += element.thickness;
+    }
+    Some(Ray::new(
+      Vec3::new(origin.x, origin.y, -origin.z),
+      Vec3::new(direction.x, direction.y, -direction.z),
+    ))
+  }
+
+  fn interface(
+    element: &LensElement,
+    element_z: f64,
+    origin: Vec3,
+    direction: Vec3,
+    eta_i: f64,
+    eta_t: f64,
+  ) -> Option<(Vec3, Vec3)> {
+    if element.is_stop() {
+      let t = (element_z - origin.z) / direction.z;
+      let point = origin + t * direction;
+      if t < 0. || point.x * point.x + point.y * point.y > element.aperture_radius.powi(2) {
+        return None;
+      }
+      return Some((point, direction));
+    }
+
+    let radius = element.curvature_radius;
+    let center = Vec3::new(0., 0., element_z + radius);
+    let oc = origin - center;
+    let a = direction.len_squared();
+    let half_b = oc.dot(direction);
+    let c = oc.len_squared() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0. {
+      return None;
+    }
+    let root = discriminant.sqrt();
+    let (t0, t1) = ((-half_b - root) / a, (-half_b + root) / a);
+    let t = if (direction.z > 0.) ^ (radius < 0.) {
+      t0.min(t1)
+    } else {
+      t0.max(t1)
+    };
+    if t < 0. {
+      return None;
+    }
+    let point = origin + t * direction;
+    if point.x * point.x + point.y * point.y > element.aperture_radius.powi(2) {
+      return None;
+    }
+
+    let mut normal = (oc + t * direction).normalization();
+    if normal.dot(direction) > 0. {
+      normal = -normal;
+    }
+    let unit = direction.normalization();
+    let ratio = eta_i / eta_t;
+    let cos_theta = (-unit).dot(normal).min(1.);
+    if ratio * ratio * (1. - cos_theta * cos_theta) > 1. {
+      return None;
+    }
+    Some((point, unit.refract(normal, ratio)))
+  }
+
+  fn cardinal_points(incoming: Ray, outgoing: Ray) -> (f64, f64) {
+    let tf = -outgoing.origin.x / outgoing.direction.x;
+    let tp = (incoming.origin.x - outgoing.origin.x) / outgoing.direction.x;
+    (-outgoing.at(tp).z, -outgoing.at(tf).z)
+  }
+
+  fn focus_thick_lens(&self, focus_distance: f64) -> Option<f64> {
+    let x = 0.001 * self.film_diagonal;
+    let scene = Ray::new(Vec3::new(x, 0., self.front_z() + 1.), Vec3::new(0., 0., -1.));
+    let (pz0, fz0) = Self::cardinal_points(scene, self.trace_from_scene(scene)?);
+    let film = Ray::new(Vec3::new(x, 0., self.rear_z() - 1.), Vec3::new(0., 0., 1.));
+    let (pz1, _) = Self::cardinal_points(film, self.trace_from_film(film)?);
+
+    let f = fz0 - pz0;
+    let z = -focus_distance;
+    let c = (pz1 - z - pz0) * (pz1 - z - 4. * f - pz0);
+    if c <= 0. {
+      return None;
+    }
+    let delta = 0.5 * (pz1 - z + pz0 - c.sqrt());
+    let thickness = self.rear_z() + delta;
+    (thickness.is_finite() && thickness > 0.).then_some(thickness)
+  }
+
+  fn bound_exit_pupil(&self, r0: f64, r1: f64) -> Aabb {
+    let extent = 1.5 * self.rear_radius();
+    let rear_bounds = Aabb::new(Vec3::new(-extent, -extent, 0.), Vec3::new(extent, extent, 0.));
+    let mut pupil = Aabb::empty();
+    let mut exiting = false;
+    for i in 0..EXIT_PUPIL_SAMPLES {
+      let film = Vec3::new(r0 + (i as f64 + 0.5) / EXIT_PUPIL_SAMPLES as f64 * (r1 - r0), 0., 0.);
+      let rear = Vec3::new(
+        -extent + 2. * extent * radical_inverse(2, i),
+        -extent + 2. * extent * radical_inverse(3, i),
+        self.rear_z(),
+      );
+      let point = Vec3::new(rear.x, rear.y, 0.);
+      if pupil.contains(point) || self.trace_from_film(Ray::new(film, rear - film)).is_some() {
+        pupil = pupil.union(&Aabb::point(point));
+        exiting = true;
+      }
+    }
+    if !exiting {
+      return rear_bounds;
+    }
+    let diagonal = rear_bounds.size().len();
+    pupil.pad(4. * diagonal / (EXIT_PUPIL_SAMPLES as f64).sqrt())
+  }
+
+  fn sample_exit_pupil(&self, film: Vec3, u: f64, v: f64) -> (Vec3, f64) {
+    let radius = (film.x * film.x + film.y * film.y).sqrt();
+    let index = (radius / (0.5 * self.film_diagonal) * EXIT_PUPIL_BOUNDS as f64) as usize;
+    let bounds = self.exit_pupil_bounds[index.min(EXIT_PUPIL_BOUNDS - 1)];
+    let size = bounds.size();
+    let (x, y) = (bounds.min.x + u * size.x, bounds.min.y + v * size.y);
+    let (sin_theta, cos_theta) = if radius > 0. {
+      (film.y / radius, film.x / radius)
+    } else {
+      (0., 1.)
+    };
+    (
+      Vec3::new(cos_theta * x - sin_theta * y, sin_theta * x + cos_theta * y, self.rear_z()),
+      size.x * size.y,
+    )
+  }
+}
+
+fn radical_inverse(base: usize, mut index: usize) -> f64 {
+  let inverse_base = 1. / base as f64;
+  let (mut reversed, mut factor) = (0., inverse_base);
+  while index > 0 {
+    reversed += (index % base) as f64 * factor;
+    index /= base;
+    factor *= inverse_base;
+  }
+  reversed
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DOUBLE_GAUSS: &str = "
+    # radius  thickness  ior  aperture
+    29.475  3.76   1.67   25.2
+    84.83   0.12   1      25.2
+    19.275  4.025  1.67   23
+    40.77   3.275  1.699  23
+    12.75   5.705  1      18
+    0       4.5    0      17.1
+    -14.495 1.18   1.603  17
+    40.77   6.065  1.658  20
+    -20.385 0.19   1      20
+    437.065 3.22   1.717  20
+    -39.73  5      1      20
+  ";
+
+  fn lens(focus_distance: f64) -> RealisticLens {
+    RealisticLens::new(RealisticLens::parse(DOUBLE_GAUSS).unwrap(), 35., focus_distance).unwrap()
+  }
+
+  fn spread(lens: &RealisticLens, distance: f64) -> f64 {
+    (0..1024)
+      .filter_map(|i| lens.generate_ray(Vec3::zero(), radical_inverse(2, i), radical_inverse(3, i)))
+      .map(|(ray, _)| {
+        let point = ray.at((distance - ray.origin.z) / ray.direction.z);
+        (point.x * point.x + point.y * point.y).sqrt()
+      })
+      .fold(0., f64::max)
+  }
+
+  #[test]
+  fn focuses_on_axis_points() {
+    let near = lens(0.5);
+    let far = lens(5.);
+    assert!(near.rear_z() > far.rear_z());
+    let focused = lens(1.);
+    let in_focus = spread(&focused, 1.);
+    assert!(in_focus < 1e-3);
+    assert!(4. * in_focus < spread(&focused, 0.5));
+    assert!(4. * in_focus < spread(&focused, 3.));
+  }
+
+  #[test]
+  fn exit_pupil_bounds_the_rear_element() {
+    let lens = lens(1.);
+    let bounds = lens.exit_pupil_bounds[0];
+    let extent = 1.5 * lens.rear_radius();
+    assert!(bounds.min.x >= -extent && bounds.max.x <= extent);
+    assert!(bounds.size().x > lens.rear_radius());
+    let hits = (0..1024)
+      .filter(|&i| {
+        let (u, v) = (radical_inverse(2, i), radical_inverse(3, i));
+        lens.generate_ray(Vec3::zero(), u, v).is_some()
+      })
+      .count();
+    assert!(hits > 512);
+  }
+
+  #[test]
+  fn rejects_invalid_prescriptions() {
+    let invalid = |source: &str| RealisticLens::parse(source).err().map(|error| error.kind());
+    assert_eq!(invalid(""), Some(io::ErrorKind::InvalidData));
+    assert_eq!(invalid("# only a comment\n"), Some(io::ErrorKind::InvalidData));
+    assert_eq!(invalid("29.475 3.76 1.67\n"), Some(io::ErrorKind::InvalidData));
+    assert_eq!(invalid("29.475 3.76 glass 25.2\n"), Some(io::ErrorKind::InvalidData));
+    let elements = RealisticLens::parse(DOUBLE_GAUSS).unwrap();
+    assert!(RealisticLens::new(elements.clone(), 35., 0.01).is_err());
+    assert!(RealisticLens::new(elements, 0., 1.).is_err());
+    assert!(RealisticLens::new(Vec::new(), 35., 1.).is_err());
+  }
+
+  #[test]
+  fn focusing_moves_only_the_rear_element() {
+    let elements = RealisticLens::parse(DOUBLE_GAUSS).unwrap();
+    let near = lens(0.5);
+    let (rear, front) = near.elements().split_last().unwrap();
+    assert_eq!(near.elements().len(), elements.len());
+    assert!(
+      front
+        .iter()
+        .zip(&elements)
+        .all(|(a, b)| a.thickness == b.thickness)
+    );
+    assert_ne!(rear.thickness, elements.last().unwrap().thickness);
+  }
+}
@@ -0,0 +1,89 @@
+use rand::Rng;
+
+use crate::algorithm::random;
+
+#[derive(Debug, Clone, Copy)]
+struct Bin {
+  q: f64,
+  p: f64,
+  alias: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AliasTable {
+  bins: Vec<Bin>,
+}
+
+impl AliasTable {
+  pub fn new(weights: &[f64]) -> AliasTable {
+    let n = weights.len();
+    let total: f64 = weights.iter().map(|w| w.max(0.)).sum();
+    let mut bins: Vec<Bin> = weights
+      .iter()
+      .map(|w| Bin {
+        q: 0.,
+        p: if total > 0. {
+          w.max(0.) / total
+        } else {
+          1. / n as f64
+        },
+        alias: 0,
+      })
+      .collect();
+
+    let mut under = Vec::new();
+    let mut over = Vec::new();
+    for (i, bin) in bins.iter().enumerate() {
+      let p = bin.p * n as f64;
+      if p < 1. {
+        under.push((i, p));
+      } else {
+        over.push((i, p));
+      }
+    }
+    while let (Some(&(small, p_small)), Some(&(large, p_large))) = (under.last(), over.last()) {
+      under.pop();
+      over.pop();
+      bins[small].q = p_small;
+      bins[small].alias = large;
+      let p_excess = p_small + p_large - 1.;
+      if p_excess < 1. {
+        under.push((large, p_excess));
+      } else {
+        over.push((large, p_excess));
+      }
+    }
+    for (i, _) in under.into_iter().chain(over) {
+      bins[i].q = 1.;
+      bins[i].alias = i;
+    }
+    AliasTable { bins }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.bins.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.bins.len()
+  }
+
+  pub fn pmf(&self, index: usize) -> f64 {
+    self.bins.get(index).map_or(0., |bin| bin.p)
+  }
+
+  pub fn sample(&self) -> Option<(usize, f64)> {
+    if self.bins.is_empty() {
+      return None;
+    }
+    let mut rng = random::rng();
+    let index = rng.random_range(0..self.bins.len());
+    let bin = &self.bins[index];
+    let index = if rng.random::<f64>() < bin.q {
+      index
+    } else {
+      bin.alias
+    };
+    Some((index, self.bins[index].p))
+  }
+}
@@ -0,0 +1,125 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::hittable::{HitRecord, Hittable};
+use crate::algorithm::interval::Interval;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Light, LightBounds, LightSample};
+
+#[derive(Clone)]
+pub struct AreaLight {
+  pub shape: Arc<dyn Hittable>,
+  emission: Color,
+}
+
+impl AreaLight {
+  pub fn new(shape: Arc<dyn Hittable>) -> AreaLight {
+    let emission = shape.emission();
+    AreaLight { shape, emission }
+  }
+
+  pub fn emission(&self) -> Color {
+    self.emission
+  }
+}
+
+impl Light for AreaLight {
+  fn sample(&self, point: Vec3) -> Option<LightSample> {
+    let direction = self.shape.random(point).normalization();
+    let pdf = self.shape.pdf_value(point, direction);
+    if pdf <= 0. {
+      return None;
+    }
+    let ray = Ray::new(point, direction);
+    let record = self.shape.hit(ray, Interval::new(0.001, f64::INFINITY))?;
+    Some(LightSample {
+      direction,
+      distance: record.t,
+      radiance: record.material.emitted(&ray, &record),
+      pdf,
+    })
+  }
+
+  fn pdf(&self, point: Vec3, direction: Vec3) -> f64 {
+    self.shape.pdf_value(point, direction)
+  }
+
+  fn pdf_hit(&self, ray: &Ray, hit_record: &HitRecord) -> f64 {
+    let Some(record) = self.shape.hit(*ray, Interval::new(0.001, f64::INFINITY)) else {
+      return 0.;
+    };
+    if (record.t - hit_record.t).abs() > 1e-6 * hit_record.t.max(1.) {
+      return 0.;
+    }
+    self.shape.pdf_value(ray.origin, ray.direction)
+  }
+
+  fn is_delta(&self) -> bool {
+    false
+  }
+
+  fn power(&self) -> f64 {
+    PI * self.shape.area() * luminance(self.emission)
+  }
+
+  fn bounds(&self) -> Option<LightBounds> {
+    let bounds = self.shape.bounding_box()?;
+    Some(LightBounds::new(
+      bounds,
+      self.shape.normal_bounds(),
+      self.shape.area() * luminance(self.emission),
+      0.,
+      false,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::hittable_list::HittableList;
+  use crate::algorithm::material::{DiffuseLight, Lambertian, MixMaterial};
+  use crate::algorithm::quad::Quad;
+
+  #[test]
+  fn emission_comes_from_the_shape_material() {
+    let lamp = Quad::new(
+      Vec3::new(-1., 2., 1.),
+      Vec3::new(2., 0., 0.),
+      Vec3::new(0., 0., -2.),
+      Arc::new(DiffuseLight::new(Color::new(4., 2., 1.))),
+    );
+    let light = AreaLight::new(Arc::new(lamp));
+    let emission = light.emission();
+    assert_eq!((emission.x, emission.y, emission.z), (4., 2., 1.));
+    assert!((light.power() - PI * 4. * luminance(emission)).abs() < 1e-9);
+  }
+
+  #[test]
+  fn mixed_and_grouped_emitters_have_a_fixed_power() {
+    let glowing = MixMaterial::new(
+      Arc::new(Lambertian::default()),
+      Arc::new(DiffuseLight::new(Color::new(8., 8., 8.))),
+      0.25,
+    );
+    let small =
+      Quad::new(Vec3::zero(), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), Arc::new(glowing));
+    let large = Quad::new(
+      Vec3::new(0., 0., 1.),
+      Vec3::new(3., 0., 0.),
+      Vec3::new(0., 1., 0.),
+      Arc::new(Lambertian::default()),
+    );
+    let mut group = HittableList::new();
+    group.add(small);
+    group.add(large);
+    let group: Arc<dyn Hittable> = Arc::new(group);
+    let light = AreaLight::new(group.clone());
+    assert!((light.emission().x - 0.5).abs() < 1e-12);
+    assert!((light.power() - PI * 4. * 0.5).abs() < 1e-9);
+    assert_eq!(light.power(), AreaLight::new(group).power());
+  }
+}
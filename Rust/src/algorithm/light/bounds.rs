@@ -0,0 +1,119 @@
+use std::f64::consts::PI;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::direction_cone::DirectionCone;
+use crate::algorithm::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LightBounds {
+  pub bounds: Aabb,
+  pub w: Vec3,
+  pub phi: f64,
+  pub cos_theta_o: f64,
+  pub cos_theta_e: f64,
+  pub two_sided: bool,
+}
+
+impl LightBounds {
+  pub fn new(
+    bounds: Aabb,
+    cone: DirectionCone,
+    phi: f64,
+    cos_theta_e: f64,
+    two_sided: bool,
+  ) -> LightBounds {
+    LightBounds {
+      bounds,
+      w: cone.w,
+      phi,
+      cos_theta_o: cone.cos_theta,
+      cos_theta_e,
+      two_sided,
+    }
+  }
+
+  pub fn centroid(&self) -> Vec3 {
+    self.bounds.centroid()
+  }
+
+  pub fn importance(&self, point: Vec3) -> f64 {
+    let center = self.bounds.centroid();
+    let offset = point - center;
+    let distance_squared = offset.len_squared().max(0.5 * self.bounds.size().len());
+    let mut cos_theta_w = if offset.len_squared() > 0. {
+      self.w.dot(offset.normalization())
+    } else {
+      1.
+    };
+    if self.two_sided {
+      cos_theta_w = cos_theta_w.abs();
+    }
+    let sin_theta_w = safe_sqrt(1. - cos_theta_w * cos_theta_w);
+
+    let cos_theta_b = DirectionCone::bound_subtended_directions(&self.bounds, point).cos_theta;
+    let sin_theta_b = safe_sqrt(1. - cos_theta_b * cos_theta_b);
+
+    let sin_theta_o = safe_sqrt(1. - self.cos_theta_o * self.cos_theta_o);
+    let cos_theta_x = cos_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+    let sin_theta_x = sin_sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+    let cos_theta_p = cos_sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+    if cos_theta_p <= self.cos_theta_e {
+      return 0.;
+    }
+    self.phi * cos_theta_p / distance_squared
+  }
+
+  pub fn orientation_measure(&self) -> f64 {
+    let theta_o = self.cos_theta_o.clamp(-1., 1.).acos();
+    let theta_e = self.cos_theta_e.clamp(-1., 1.).acos();
+    let theta_w = (theta_o + theta_e).min(PI);
+    let sin_theta_o = safe_sqrt(1. - self.cos_theta_o * self.cos_theta_o);
+    2. * PI * (1. - self.cos_theta_o)
+      + PI / 2.
+        * (2. * theta_w * sin_theta_o - (theta_o - 2. * theta_w).cos() - 2. * theta_o * sin_theta_o
+          + self.cos_theta_o)
+  }
+
+  pub fn union(&self, other: &LightBounds) -> LightBounds {
+    if self.phi == 0. {
+      return *other;
+    }
+    if other.phi == 0. {
+      return *self;
+    }
+    let cone = DirectionCone {
+      w: self.w,
+      cos_theta: self.cos_theta_o,
+    }
+    .union(&DirectionCone {
+      w: other.w,
+      cos_theta: other.cos_theta_o,
+    });
+    LightBounds {
+      bounds: self.bounds.union(&other.bounds),
+      w: cone.w,
+      phi: self.phi + other.phi,
+      cos_theta_o: cone.cos_theta,
+      cos_theta_e: self.cos_theta_e.min(other.cos_theta_e),
+      two_sided: self.two_sided || other.two_sided,
+    }
+  }
+}
+
+fn safe_sqrt(x: f64) -> f64 {
+  x.max(0.).sqrt()
+}
+
+fn cos_sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> f64 {
+  if cos_a > cos_b {
+    return 1.;
+  }
+  cos_a * cos_b + sin_a * sin_b
+}
+
+fn sin_sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> f64 {
+  if cos_a > cos_b {
+    return 0.;
+  }
+  sin_a * cos_b - cos_a * sin_b
+}
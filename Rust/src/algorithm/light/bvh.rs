@@ -0,0 +1,228 @@
+use rand::Rng;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::random;
+use crate::algorithm::vec3::Vec3;
+
+use super::bounds::LightBounds;
+
+const BUCKETS: usize = 12;
+const MAX_DEPTH: u32 = 32;
+
+#[derive(Debug, Clone, Copy)]
+struct LightBvhNode {
+  bounds: LightBounds,
+  child_or_light: usize,
+  is_leaf: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LightBvh {
+  nodes: Vec<LightBvhNode>,
+  bit_trails: Vec<Option<u64>>,
+}
+
+impl LightBvh {
+  pub fn new(lights: &[(usize, LightBounds)], light_count: usize) -> LightBvh {
+    let mut bvh = LightBvh {
+      nodes: Vec::new(),
+      bit_trails: vec![None; light_count],
+    };
+    let mut lights: Vec<_> = lights.iter().filter(|(_, b)| b.phi > 0.).copied().collect();
+    if !lights.is_empty() {
+      bvh.build(&mut lights, 0, 0);
+    }
+    bvh
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  pub fn sample(&self, point: Vec3) -> Option<(usize, f64)> {
+    if self.nodes.is_empty() {
+      return None;
+    }
+    let mut rng = random::rng();
+    let mut index = 0;
+    let mut pmf = 1.;
+    loop {
+      let node = &self.nodes[index];
+      if node.is_leaf {
+        if index > 0 || node.bounds.importance(point) > 0. {
+          return Some((node.child_or_light, pmf));
+        }
+        return None;
+      }
+      let children = [index + 1, node.child_or_light];
+      let importance = children.map(|child| self.nodes[child].bounds.importance(point));
+      let total = importance[0] + importance[1];
+      if total <= 0. {
+        return None;
+      }
+      let p0 = importance[0] / total;
+      if rng.random::<f64>() < p0 {
+        index = children[0];
+        pmf *= p0;
+      } else {
+        index = children[1];
+        pmf *= 1. - p0;
+      }
+    }
+  }
+
+  pub fn pmf(&self, point: Vec3, light: usize) -> f64 {
+    let Some(Some(mut bit_trail)) = self.bit_trails.get(light).copied() else {
+      return 0.;
+    };
+    let mut index = 0;
+    let mut pmf = 1.;
+    loop {
+      let node = &self.nodes[index];
+      if node.is_leaf {
+        if index > 0 || node.bounds.importance(point) > 0. {
+          return pmf;
+        }
+        return 0.;
+      }
+      let children = [index + 1, node.child_or_light];
+      let importance = children.map(|child| self.nodes[child].bounds.importance(point));
+      let total = importance[0] + importance[1];
+      if total <= 0. {
+        return 0.;
+      }
+      let side = (bit_trail & 1) as usize;
+      pmf *= importance[side] / total;
+      index = children[side];
+      bit_trail >>= 1;
+    }
+  }
+
+  pub fn lights_containing(&self, point: Vec3) -> Vec<usize> {
+    let mut lights = Vec::new();
+    let mut stack = Vec::new();
+    if !self.nodes.is_empty() {
+      stack.push(0);
+    }
+    while let Some(index) = stack.pop() {
+      let node = &self.nodes[index];
+      if !node.bounds.bounds.pad(1e-4).contains(point) {
+        continue;
+      }
+      if node.is_leaf {
+        lights.push(node.child_or_light);
+      } else {
+        stack.push(index + 1);
+        stack.push(node.child_or_light);
+      }
+    }
+    lights
+  }
+
+  fn build(&mut self, lights: &mut [(usize, LightBounds)], bit_trail: u64, depth: u32) -> usize {
+    let index = self.nodes.len();
+    if lights.len() == 1 {
+      let (light, bounds) = lights[0];
+      self.nodes.push(LightBvhNode {
+        bounds,
+        child_or_light: light,
+        is_leaf: true,
+      });
+      self.bit_trails[light] = Some(bit_trail);
+      return index;
+    }
+
+    let bounds = lights
+      .iter()
+      .fold(Aabb::empty(), |bounds, (_, b)| bounds.union(&b.bounds));
+    let centroid_bounds = lights
+      .iter()
+      .fold(Aabb::empty(), |bounds, (_, b)| bounds.union(&Aabb::point(b.centroid())));
+
+    let mid = match Self::best_split(lights, &bounds, &centroid_bounds) {
+      Some((dim, split)) if depth < MAX_DEPTH => {
+        let bucket = |b: &LightBounds| Self::bucket(b, &centroid_bounds, dim);
+        let mut mid = 0;
+        for i in 0..lights.len() {
+          if bucket(&lights[i].1) <= split {
+            lights.swap(i, mid);
+            mid += 1;
+          }
+        }
+        if mid == 0 || mid == lights.len() {
+          lights.len() / 2
+        } else {
+          mid
+        }
+      }
+      _ => lights.len() / 2,
+    };
+
+    self.nodes.push(LightBvhNode {
+      bounds: lights[0].1,
+      child_or_light: 0,
+      is_leaf: false,
+    });
+    let (left, right) = lights.split_at_mut(mid);
+    let left = self.build(left, bit_trail, depth + 1);
+    let right = self.build(right, bit_trail | (1 << depth), depth + 1);
+    self.nodes[index] = LightBvhNode {
+      bounds: self.nodes[left].bounds.union(&self.nodes[right].bounds),
+      child_or_light: right,
+      is_leaf: false,
+    };
+    index
+  }
+
+  fn bucket(bounds: &LightBounds, centroid_bounds: &Aabb, dim: usize) -> usize {
+    let axis = |v: Vec3| [v.x, v.y, v.z][dim];
+    let (min, max) = (axis(centroid_bounds.min), axis(centroid_bounds.max));
+    let offset = (axis(bounds.centroid()) - min) / (max - min);
+    ((offset * BUCKETS as f64) as usize).min(BUCKETS - 1)
+  }
+
+  fn best_split(
+    lights: &[(usize, LightBounds)],
+    bounds: &Aabb,
+    centroid_bounds: &Aabb,
+  ) -> Option<(usize, usize)> {
+    let size = bounds.size();
+    let extent = [size.x, size.y, size.z];
+    let centroid_extent = centroid_bounds.size();
+    let centroid_extent = [centroid_extent.x, centroid_extent.y, centroid_extent.z];
+    let max_extent = extent.iter().fold(0f64, |m, e| m.max(*e));
+
+    let mut best: Option<(f64, usize, usize)> = None;
+    for dim in 0..3 {
+      if centroid_extent[dim] <= 0. {
+        continue;
+      }
+      let mut buckets: [Option<LightBounds>; BUCKETS] = [None; BUCKETS];
+      for (_, b) in lights {
+        let bucket = &mut buckets[Self::bucket(b, centroid_bounds, dim)];
+        *bucket = Some(bucket.map_or(*b, |existing| existing.union(b)));
+      }
+      let regularization = if extent[dim] > 0. {
+        max_extent / extent[dim]
+      } else {
+        1.
+      };
+      let cost = |b: Option<LightBounds>| {
+        b.map_or(0., |b| b.phi * b.orientation_measure() * regularization * b.bounds.surface_area())
+      };
+      let union = |range: &[Option<LightBounds>]| {
+        range
+          .iter()
+          .flatten()
+          .fold(None, |acc: Option<LightBounds>, b| Some(acc.map_or(*b, |acc| acc.union(b))))
+      };
+      for split in 0..BUCKETS - 1 {
+        let total = cost(union(&buckets[..=split])) + cost(union(&buckets[split + 1..]));
+        if best.is_none_or(|(best_cost, _, _)| total < best_cost) {
+          best = Some((total, dim, split));
+        }
+      }
+    }
+    best.map(|(_, dim, split)| (dim, split))
+  }
+}
@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::onb::Onb;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Light, LightSample};
+
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+  pub direction: Vec3,
+  pub irradiance: Color,
+  pub angular_diameter: f64,
+}
+
+impl DirectionalLight {
+  pub fn new(direction: Vec3, irradiance: Color) -> DirectionalLight {
+    Self::with_angular_diameter(direction, irradiance, 0.)
+  }
+
+  pub fn with_angular_diameter(
+    direction: Vec3,
+    irradiance: Color,
+    angular_diameter: f64,
+  ) -> DirectionalLight {
+    DirectionalLight {
+      direction: direction.normalization(),
+      irradiance,
+      angular_diameter,
+    }
+  }
+
+  pub fn sun(direction: Vec3, irradiance: Color) -> DirectionalLight {
+    Self::with_angular_diameter(direction, irradiance, 0.53)
+  }
+
+  fn cos_theta_max(&self) -> f64 {
+    (0.5 * self.angular_diameter).to_radians().cos()
+  }
+
+  fn solid_angle(&self) -> f64 {
+    2. * PI * (1. - self.cos_theta_max())
+  }
+}
+
+impl Light for DirectionalLight {
+  fn sample(&self, _point: Vec3) -> Option<LightSample> {
+    if self.is_delta() {
+      return Some(LightSample {
+        direction: self.direction,
+        distance: f64::INFINITY,
+        radiance: self.irradiance,
+        pdf: 1.,
+      });
+    }
+    let frame = Onb::new(self.direction);
+    let solid_angle = self.solid_angle();
+    Some(LightSample {
+      direction: frame.transform(Vec3::random_in_cone(self.cos_theta_max())),
+      distance: f64::INFINITY,
+      radiance: self.irradiance / solid_angle,
+      pdf: 1. / solid_angle,
+    })
+  }
+
+  fn pdf(&self, _point: Vec3, direction: Vec3) -> f64 {
+    if self.is_delta() || direction.normalization().dot(self.direction) < self.cos_theta_max() {
+      return 0.;
+    }
+    1. / self.solid_angle()
+  }
+
+  fn emitted(&self, ray: &Ray) -> Color {
+    if self.is_delta() || ray.direction.normalization().dot(self.direction) < self.cos_theta_max() {
+      return Color::zero();
+    }
+    self.irradiance / self.solid_angle()
+  }
+
+  fn is_delta(&self) -> bool {
+    self.angular_diameter <= 0.
+  }
+
+  fn power(&self) -> f64 {
+    luminance(self.irradiance)
+  }
+}
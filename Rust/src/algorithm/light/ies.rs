@@ -0,0 +1,271 @@
+use std::f64::consts::PI;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::direction_cone::DirectionCone;
+use crate::algorithm::onb::Onb;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Light, LightBounds, LightSample};
+
+const MAX_ANGLES: usize = 1 << 12;
+
+#[derive(Debug, Clone)]
+pub struct IesProfile {
+  pub vertical_angles: Vec<f64>,
+  pub horizontal_angles: Vec<f64>,
+  pub candela: Vec<Vec<f64>>,
+}
+
+impl IesProfile {
+  pub fn load<P: AsRef<Path>>(path: P) -> io::Result<IesProfile> {
+    Self::parse(&fs::read_to_string(path)?)
+  }
+
+  pub fn parse(source: &str) -> io::Result<IesProfile> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut lines = source.lines();
+    let tilt = lines
+      .by_ref()
+      .find(|line| line.trim_start().starts_with("TILT="))
+      .ok_or_else(|| invalid("missing TILT line"))?;
+    let rest = lines.collect::<Vec<_>>().join(" ");
+    let mut values = rest
+      .split(|c: char| c.is_whitespace() || c == ',')
+      .filter(|token| !token.is_empty())
+      .map(|token| token.parse::<f64>());
+    let mut next = || -> io::Result<f64> {
+      values
+        .next()
+        .ok_or_else(|| invalid("unexpected end of photometric data"))?
+        .map_err(|_| invalid("malformed number in photometric data"))
+    };
+
+    let count = |value: f64| -> io::Result<usize> {
+      if value.is_finite() && value >= 0. && value <= MAX_ANGLES as f64 {
+        Ok(value as usize)
+      } else {
+        Err(invalid("angle count out of range"))
+      }
+    };
+
+    if tilt.trim() == "TILT=INCLUDE" {
+      next()?;
+      let pairs = count(next()?)?
+        .checked_mul(2)
+        .ok_or_else(|| invalid("angle count out of range"))?;
+      for _ in 0..pairs {
+        next()?;
+      }
+    }
+
+    let _lamps = next()?;
+    let _lumens = next()?;
+    let multiplier = next()?;
+    let vertical_count = count(next()?)?;
+    let horizontal_count = count(next()?)?;
+    let photometric_type = next()? as i32;
+    for _ in 0..7 {
+      next()?;
+    }
+    if photometric_type != 1 {
+      return Err(invalid("only type C photometry is supported"));
+    }
+    if vertical_count == 0 || horizontal_count == 0 {
+      return Err(invalid("photometric data has no angles"));
+    }
+    if !multiplier.is_finite() {
+      return Err(invalid("candela multiplier is not finite"));
+    }
+
+    let vertical_angles = (0..vertical_count)
+      .map(|_| next())
+      .collect::<io::Result<Vec<_>>>()?;
+    let horizontal_angles = (0..horizontal_count)
+      .map(|_| next())
+      .collect::<io::Result<Vec<_>>>()?;
+    let increasing = |angles: &[f64]| {
+      angles.iter().all(|angle| angle.is_finite())
+        && angles.windows(2).all(|pair| pair[0] < pair[1])
+    };
+    if !increasing(&vertical_angles) || !increasing(&horizontal_angles) {
+      return Err(invalid("photometric angles must increase monotonically"));
+    }
+    let candela = (0..horizontal_count)
+      .map(|_| {
+        (0..vertical_count)
+          .map(|_| next().map(|value| value * multiplier))
+          .collect::<io::Result<Vec<_>>>()
+      })
+      .collect::<io::Result<Vec<_>>>()?;
+    Ok(IesProfile {
+      vertical_angles,
+      horizontal_angles,
+      candela,
+    })
+  }
+
+  pub fn intensity(&self, theta: f64, phi: f64) -> f64 {
+    let last = *self.horizontal_angles.last().unwrap_or(&0.);
+    let mut phi = phi.rem_euclid(360.);
+    if last <= 90. && phi > 180. {
+      phi = 360. - phi;
+    }
+    if last <= 90. && phi > 90. {
+      phi = 180. - phi;
+    } else if last <= 180. && phi > 180. {
+      phi = 360. - phi;
+    }
+
+    let (h0, h1, t) = Self::bracket(&self.horizontal_angles, phi);
+    (1. - t) * self.vertical(h0, theta) + t * self.vertical(h1, theta)
+  }
+
+  pub fn power(&self) -> f64 {
+    let (theta_steps, phi_steps) = (90, 180);
+    let d_theta = PI / theta_steps as f64;
+    let d_phi = 2. * PI / phi_steps as f64;
+    let mut total = 0.;
+    for i in 0..theta_steps {
+      let theta = (i as f64 + 0.5) * d_theta;
+      for j in 0..phi_steps {
+        let phi = (j as f64 + 0.5) * d_phi;
+        total += self.intensity(theta.to_degrees(), phi.to_degrees()) * theta.sin();
+      }
+    }
+    total * d_theta * d_phi
+  }
+
+  pub fn max_intensity(&self) -> f64 {
+    self
+      .candela
+      .iter()
+      .flatten()
+      .fold(0f64, |max, value| max.max(*value))
+  }
+
+  fn vertical(&self, row: usize, theta: f64) -> f64 {
+    let first = self.vertical_angles[0];
+    let last = self.vertical_angles[self.vertical_angles.len() - 1];
+    if theta < first || theta > last {
+      return 0.;
+    }
+    let (v0, v1, t) = Self::bracket(&self.vertical_angles, theta);
+    (1. - t) * self.candela[row][v0] + t * self.candela[row][v1]
+  }
+
+  fn bracket(angles: &[f64], angle: f64) -> (usize, usize, f64) {
+    let upper = angles.partition_point(|a| *a <= angle);
+    if upper == 0 {
+      return (0, 0, 0.);
+    }
+    if upper == angles.len() {
+      return (upper - 1, upper - 1, 0.);
+    }
+    let (a0, a1) = (angles[upper - 1], angles[upper]);
+    (upper - 1, upper, (angle - a0) / (a1 - a0))
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct IesLight {
+  pub position: Vec3,
+  pub frame: Onb,
+  pub profile: IesProfile,
+  pub scale: Color,
+}
+
+impl IesLight {
+  pub fn new(position: Vec3, direction: Vec3, profile: IesProfile, scale: Color) -> IesLight {
+    IesLight {
+      position,
+      frame: Onb::new(direction.normalization()),
+      profile,
+      scale,
+    }
+  }
+}
+
+impl Light for IesLight {
+  fn sample(&self, point: Vec3) -> Option<LightSample> {
+    let offset = self.position - point;
+    let distance_squared = offset.len_squared();
+    if distance_squared <= 0. {
+      return None;
+    }
+    let distance = distance_squared.sqrt();
+    let direction = offset / distance;
+    let local = self.frame.to_local(-direction);
+    let theta = local.z.clamp(-1., 1.).acos().to_degrees();
+    let phi = local.y.atan2(local.x).to_degrees();
+    let intensity = self.profile.intensity(theta, phi);
+    if intensity <= 0. {
+      return None;
+    }
+    Some(LightSample {
+      direction,
+      distance,
+      radiance: intensity * self.scale / distance_squared,
+      pdf: 1.,
+    })
+  }
+
+  fn power(&self) -> f64 {
+    self.profile.power() * luminance(self.scale)
+  }
+
+  fn bounds(&self) -> Option<LightBounds> {
+    Some(LightBounds::new(
+      Aabb::point(self.position),
+      DirectionCone::entire_sphere(),
+      4. * PI * self.profile.max_intensity() * luminance(self.scale),
+      0.,
+      false,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn source(tilt: &str, counts: &str, vertical: &str) -> String {
+    format!(
+      "IESNA:LM-63-2002\n{tilt}\n1 1000 1 {counts} 1 1 0 0 0\n1 1 100\n{vertical}\n0\n100 50 0\n"
+    )
+  }
+
+  #[test]
+  fn parses_type_c_profiles() {
+    let profile = IesProfile::parse(&source("TILT=NONE", "3 1", "0 45 90")).unwrap();
+    assert_eq!(profile.vertical_angles, vec![0., 45., 90.]);
+    assert_eq!(profile.candela, vec![vec![100., 50., 0.]]);
+    assert_eq!(profile.intensity(22.5, 0.), 75.);
+
+    let tilted = source("TILT=INCLUDE\n1\n2\n0 90\n1 1", "3 1", "0 45 90");
+    assert!(IesProfile::parse(&tilted).is_ok());
+  }
+
+  #[test]
+  fn rejects_malformed_profiles() {
+    let malformed = [
+      source("TILT=INCLUDE\n1\n1e300\n0 90\n1 1", "3 1", "0 45 90"),
+      source("TILT=INCLUDE\n1\n-1\n0 90\n1 1", "3 1", "0 45 90"),
+      source("TILT=INCLUDE\n1\nNaN\n0 90\n1 1", "3 1", "0 45 90"),
+      source("TILT=NONE", "1e12 1", "0 45 90"),
+      source("TILT=NONE", "3 -1", "0 45 90"),
+      source("TILT=NONE", "inf 1", "0 45 90"),
+      source("TILT=NONE", "3 1", "0 90 45"),
+      source("TILT=NONE", "3 1", "0 45 45"),
+      source("TILT=NONE", "3 1", "0 NaN 90"),
+      source("TILT=NONE", "4 1", "0 45 90"),
+    ];
+    for source in malformed {
+      let error = IesProfile::parse(&source).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{source}");
+    }
+  }
+}
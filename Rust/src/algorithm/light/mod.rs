@@ -0,0 +1,387 @@
+use std::sync::{Arc, OnceLock};
+
+use rand::Rng;
+
+use super::color::Color;
+use super::hittable::HitRecord;
+use super::random;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+mod alias;
+mod area;
+mod bounds;
+mod bvh;
+mod directional;
+mod ies;
+mod point;
+mod spot;
+
+pub use alias::AliasTable;
+pub use area::AreaLight;
+pub use bounds::LightBounds;
+pub use bvh::LightBvh;
+pub use directional::DirectionalLight;
+pub use ies::{IesLight, IesProfile};
+pub use point::PointLight;
+pub use spot::SpotLight;
+
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+  pub direction: Vec3,
+  pub distance: f64,
+  pub radiance: Color,
+  pub pdf: f64,
+}
+
+pub trait Light: Send + Sync {
+  fn sample(&self, point: Vec3) -> Option<LightSample>;
+
+  fn pdf(&self, _point: Vec3, _direction: Vec3) -> f64 {
+    0.
+  }
+
+  fn emitted(&self, _ray: &Ray) -> Color {
+    Color::zero()
+  }
+
+  fn pdf_hit(&self, _ray: &Ray, _hit_record: &HitRecord) -> f64 {
+    0.
+  }
+
+  fn is_delta(&self) -> bool {
+    true
+  }
+
+  fn power(&self) -> f64;
+
+  fn bounds(&self) -> Option<LightBounds> {
+    None
+  }
+}
+
+impl<L> Light for Arc<L>
+where
+  L: Light + ?Sized,
+{
+  fn sample(&self, point: Vec3) -> Option<LightSample> {
+    (**self).sample(point)
+  }
+
+  fn pdf(&self, point: Vec3, direction: Vec3) -> f64 {
+    (**self).pdf(point, direction)
+  }
+
+  fn emitted(&self, ray: &Ray) -> Color {
+    (**self).emitted(ray)
+  }
+
+  fn pdf_hit(&self, ray: &Ray, hit_record: &HitRecord) -> f64 {
+    (**self).pdf_hit(ray, hit_record)
+  }
+
+  fn is_delta(&self) -> bool {
+    (**self).is_delta()
+  }
+
+  fn power(&self) -> f64 {
+    (**self).power()
+  }
+
+  fn bounds(&self) -> Option<LightBounds> {
+    (**self).bounds()
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LightSampling {
+  Uniform,
+  Power,
+  #[default]
+  Bvh,
+}
+
+#[derive(Clone)]
+struct LightSampler {
+  power: Option<AliasTable>,
+  bvh: LightBvh,
+  infinite: Vec<usize>,
+}
+
+#[derive(Clone, Default)]
+pub struct LightList {
+  lights: Vec<Arc<dyn Light>>,
+  sampling: LightSampling,
+  sampler: OnceLock<LightSampler>,
+}
+
+impl LightList {
+  pub fn new(sampling: LightSampling) -> LightList {
+    LightList {
+      sampling,
+      ..Default::default()
+    }
+  }
+
+  pub fn add<L: Light + 'static>(&mut self, light: L) {
+    self.add_shared(Arc::new(light));
+  }
+
+  pub fn add_shared(&mut self, light: Arc<dyn Light>) {
+    self.lights.push(light);
+    self.sampler = OnceLock::new();
+  }
+
+  pub fn clear(&mut self) {
+    self.lights.clear();
+    self.sampler = OnceLock::new();
+  }
+
+  pub fn lights(&self) -> &[Arc<dyn Light>] {
+    &self.lights
+  }
+
+  pub fn sampling(&self) -> LightSampling {
+    self.sampling
+  }
+
+  pub fn set_sampling(&mut self, sampling: LightSampling) {
+    self.sampling = sampling;
+    self.sampler = OnceLock::new();
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.lights.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.lights.len()
+  }
+
+  pub fn sample(&self, point: Vec3) -> Option<(&dyn Light, LightSample)> {
+    let (index, pmf) = self.sample_index(point)?;
+    let light = self.lights[index].as_ref();
+    let mut sample = light.sample(point)?;
+    sample.pdf *= pmf;
+    Some((light, sample))
+  }
+
+  pub fn pmf(&self, point: Vec3, index: usize) -> f64 {
+    if index >= self.lights.len() {
+      return 0.;
+    }
+    let sampler = self.sampler();
+    match self.sampling {
+      LightSampling::Uniform => 1. / self.lights.len() as f64,
+      LightSampling::Power => sampler.power.as_ref().map_or(0., |table| table.pmf(index)),
+      LightSampling::Bvh => {
+        let p_infinite = Self::infinite_probability(sampler);
+        if sampler.infinite.contains(&index) {
+          p_infinite / sampler.infinite.len() as f64
+        } else {
+          (1. - p_infinite) * sampler.bvh.pmf(point, index)
+        }
+      }
+    }
+  }
+
+  pub fn pdf(&self, point: Vec3, direction: Vec3) -> f64 {
+    self
+      .lights
+      .iter()
+      .enumerate()
+      .map(|(index, light)| {
+        let pdf = light.pdf(point, direction);
+        if pdf > 0. {
+          self.pmf(point, index) * pdf
+        } else {
+          0.
+        }
+      })
+      .sum()
+  }
+
+  pub fn pdf_hit(&self, ray: &Ray, hit_record: &HitRecord) -> f64 {
+    if self.lights.is_empty() {
+      return 0.;
+    }
+    let sampler = self.sampler();
+    sampler
+      .bvh
+      .lights_containing(hit_record.point)
+      .into_iter()
+      .chain(sampler.infinite.iter().copied())
+      .map(|index| {
+        let pdf = self.lights[index].pdf_hit(ray, hit_record);
+        if pdf > 0. {
+          self.pmf(ray.origin, index) * pdf
+        } else {
+          0.
+        }
+      })
+      .sum()
+  }
+
+  pub fn infinite(&self, point: Vec3) -> impl Iterator<Item = (&dyn Light, f64)> {
+    let indices: &[usize] = if self.lights.is_empty() {
+      &[]
+    } else {
+      &self.sampler().infinite
+    };
+    indices
+      .iter()
+      .map(move |&index| (self.lights[index].as_ref(), self.pmf(point, index)))
+  }
+
+  fn sample_index(&self, point: Vec3) -> Option<(usize, f64)> {
+    if self.lights.is_empty() {
+      return None;
+    }
+    let sampler = self.sampler();
+    let mut rng = random::rng();
+    match self.sampling {
+      LightSampling::Uniform => {
+        let index = rng.random_range(0..self.lights.len());
+        Some((index, 1. / self.lights.len() as f64))
+      }
+      LightSampling::Power => sampler.power.as_ref()?.sample(),
+      LightSampling::Bvh => {
+        let p_infinite = Self::infinite_probability(sampler);
+        if rng.random::<f64>() < p_infinite {
+          let index = sampler.infinite[rng.random_range(0..sampler.infinite.len())];
+          Some((index, p_infinite / sampler.infinite.len() as f64))
+        } else {
+          let (index, pmf) = sampler.bvh.sample(point)?;
+          Some((index, (1. - p_infinite) * pmf))
+        }
+      }
+    }
+  }
+
+  fn infinite_probability(sampler: &LightSampler) -> f64 {
+    let infinite = sampler.infinite.len() as f64;
+    let bounded = if sampler.bvh.is_empty() { 0. } else { 1. };
+    if infinite + bounded > 0. {
+      infinite / (infinite + bounded)
+    } else {
+      0.
+    }
+  }
+
+  fn sampler(&self) -> &LightSampler {
+    self.sampler.get_or_init(|| {
+      let mut bounded = Vec::new();
+      let mut infinite = Vec::new();
+      for (index, light) in self.lights.iter().enumerate() {
+        match light.bounds() {
+          Some(bounds) => bounded.push((index, bounds)),
+          None => infinite.push(index),
+        }
+      }
+      let powers: Vec<f64> = self.lights.iter().map(|light| light.power()).collect();
+      LightSampler {
+        power: (!powers.is_empty()).then(|| AliasTable::new(&powers)),
+        bvh: LightBvh::new(&bounded, self.lights.len()),
+        infinite,
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::aabb::Aabb;
+  use crate::algorithm::hittable::Hittable;
+  use crate::algorithm::interval::Interval;
+  use crate::algorithm::material::DiffuseLight;
+  use crate::algorithm::quad::Quad;
+
+  struct Unbounded(Quad);
+
+  impl Hittable for Unbounded {
+    fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+      self.0.hit(ray, interval)
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+      self.0.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: Vec3) -> Vec3 {
+      self.0.random(origin)
+    }
+
+    fn area(&self) -> f64 {
+      self.0.area()
+    }
+
+    fn emission(&self) -> Color {
+      self.0.emission()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+      None
+    }
+  }
+
+  fn lamp(y: f64, emission: f64) -> Quad {
+    Quad::new(
+      Vec3::new(-0.5, y, 0.5),
+      Vec3::new(1., 0., 0.),
+      Vec3::new(0., 0., -1.),
+      Arc::new(DiffuseLight::new(Color::new(emission, emission, emission))),
+    )
+  }
+
+  fn scene(sampling: LightSampling) -> LightList {
+    let mut lights = LightList::new(sampling);
+    lights.add(PointLight::new(Vec3::new(3., 1., 0.), Color::new(5., 5., 5.)));
+    lights.add(PointLight::new(Vec3::new(-2., 4., 1.), Color::new(1., 2., 3.)));
+    lights.add(AreaLight::new(Arc::new(lamp(2., 4.))));
+    lights.add(DirectionalLight::new(Vec3::new(0., -1., 0.), Color::one()));
+    lights
+  }
+
+  #[test]
+  fn alias_table_pmf_sums_to_one() {
+    let table = AliasTable::new(&[1., 0., 3., 0.5, 7.]);
+    let total: f64 = (0..table.len()).map(|index| table.pmf(index)).sum();
+    assert!((total - 1.).abs() < 1e-12);
+    assert_eq!(table.pmf(1), 0.);
+    assert!((table.pmf(4) - 7. / 11.5).abs() < 1e-12);
+  }
+
+  #[test]
+  fn light_pmf_sums_to_one() {
+    for sampling in [
+      LightSampling::Uniform,
+      LightSampling::Power,
+      LightSampling::Bvh,
+    ] {
+      let lights = scene(sampling);
+      for point in [Vec3::zero(), Vec3::new(2., 1., -3.), Vec3::new(0., 2.5, 0.)] {
+        let total: f64 = (0..lights.len())
+          .map(|index| lights.pmf(point, index))
+          .sum();
+        assert!((total - 1.).abs() < 1e-9, "{sampling:?} at {point:?}: {total}");
+      }
+    }
+  }
+
+  #[test]
+  fn unbounded_area_lights_report_their_pdf() {
+    let mut lights = LightList::new(LightSampling::Bvh);
+    lights.add(PointLight::new(Vec3::new(3., 1., 0.), Color::one()));
+    lights.add(AreaLight::new(Arc::new(Unbounded(lamp(2., 4.)))));
+    let ray = Ray::new(Vec3::zero(), Vec3::new(0., 1., 0.));
+    let light = &lights.lights()[1];
+    let surface = lamp(2., 4.);
+    let record = surface
+      .hit(ray, Interval::new(0.001, f64::INFINITY))
+      .unwrap();
+    let expected = lights.pmf(ray.origin, 1) * light.pdf_hit(&ray, &record);
+    assert!(expected > 0.);
+    assert!((lights.pdf_hit(&ray, &record) - expected).abs() < 1e-12);
+  }
+}
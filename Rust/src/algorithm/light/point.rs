@@ -0,0 +1,54 @@
+use std::f64::consts::PI;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::direction_cone::DirectionCone;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Light, LightBounds, LightSample};
+
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+  pub position: Vec3,
+  pub intensity: Color,
+}
+
+impl PointLight {
+  pub fn new(position: Vec3, intensity: Color) -> PointLight {
+    PointLight {
+      position,
+      intensity,
+    }
+  }
+}
+
+impl Light for PointLight {
+  fn sample(&self, point: Vec3) -> Option<LightSample> {
+    let offset = self.position - point;
+    let distance_squared = offset.len_squared();
+    if distance_squared <= 0. {
+      return None;
+    }
+    let distance = distance_squared.sqrt();
+    Some(LightSample {
+      direction: offset / distance,
+      distance,
+      radiance: self.intensity / distance_squared,
+      pdf: 1.,
+    })
+  }
+
+  fn power(&self) -> f64 {
+    4. * PI * luminance(self.intensity)
+  }
+
+  fn bounds(&self) -> Option<LightBounds> {
+    Some(LightBounds::new(
+      Aabb::point(self.position),
+      DirectionCone::entire_sphere(),
+      self.power(),
+      0.,
+      false,
+    ))
+  }
+}
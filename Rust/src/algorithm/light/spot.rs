@@ -0,0 +1,85 @@
+use std::f64::consts::PI;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::direction_cone::DirectionCone;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Light, LightBounds, LightSample};
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpotLight {
+  pub position: Vec3,
+  pub direction: Vec3,
+  pub intensity: Color,
+  pub cos_falloff_start: f64,
+  pub cos_falloff_end: f64,
+}
+
+impl SpotLight {
+  pub fn new(
+    position: Vec3,
+    look_at: Vec3,
+    intensity: Color,
+    total_width: f64,
+    falloff_start: f64,
+  ) -> SpotLight {
+    SpotLight {
+      position,
+      direction: (look_at - position).normalization(),
+      intensity,
+      cos_falloff_start: falloff_start.min(total_width).to_radians().cos(),
+      cos_falloff_end: total_width.to_radians().cos(),
+    }
+  }
+
+  fn falloff(&self, cos_theta: f64) -> f64 {
+    if cos_theta >= self.cos_falloff_start {
+      return 1.;
+    }
+    if cos_theta <= self.cos_falloff_end {
+      return 0.;
+    }
+    let t = (cos_theta - self.cos_falloff_end) / (self.cos_falloff_start - self.cos_falloff_end);
+    t * t * (3. - 2. * t)
+  }
+}
+
+impl Light for SpotLight {
+  fn sample(&self, point: Vec3) -> Option<LightSample> {
+    let offset = self.position - point;
+    let distance_squared = offset.len_squared();
+    if distance_squared <= 0. {
+      return None;
+    }
+    let distance = distance_squared.sqrt();
+    let direction = offset / distance;
+    let falloff = self.falloff(-direction.dot(self.direction));
+    if falloff <= 0. {
+      return None;
+    }
+    Some(LightSample {
+      direction,
+      distance,
+      radiance: falloff * self.intensity / distance_squared,
+      pdf: 1.,
+    })
+  }
+
+  fn power(&self) -> f64 {
+    let cone = 1. - 0.5 * (self.cos_falloff_start + self.cos_falloff_end);
+    2. * PI * cone * luminance(self.intensity)
+  }
+
+  fn bounds(&self) -> Option<LightBounds> {
+    let theta_start = self.cos_falloff_start.clamp(-1., 1.).acos();
+    let theta_end = self.cos_falloff_end.clamp(-1., 1.).acos();
+    Some(LightBounds::new(
+      Aabb::point(self.position),
+      DirectionCone::new(self.direction, self.cos_falloff_start),
+      4. * PI * luminance(self.intensity),
+      (theta_end - theta_start).cos(),
+      false,
+    ))
+  }
+}
@@ -0,0 +1,497 @@
+// Reads the scalar material factors of glTF 2.0 documents. Texture references (`*Texture`
+// properties) are ignored, so textured materials load with their factors only.
+
+use std::io;
+
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::material::Principled;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+  #[default]
+  Opaque,
+  Mask,
+  Blend,
+}
+
+#[derive(Debug, Clone)]
+pub struct GltfMaterial {
+  pub base_color_factor: [f64; 4],
+  pub metallic_factor: f64,
+  pub roughness_factor: f64,
+  pub emissive_factor: [f64; 3],
+  pub emissive_strength: f64,
+  pub ior: f64,
+  pub specular_factor: f64,
+  pub specular_color_factor: [f64; 3],
+  pub sheen_color_factor: [f64; 3],
+  pub clearcoat_factor: f64,
+  pub clearcoat_roughness_factor: f64,
+  pub transmission_factor: f64,
+  pub alpha_mode: AlphaMode,
+  pub alpha_cutoff: f64,
+}
+
+impl Default for GltfMaterial {
+  fn default() -> Self {
+    GltfMaterial {
+      base_color_factor: [1., 1., 1., 1.],
+      metallic_factor: 1.,
+      roughness_factor: 1.,
+      emissive_factor: [0., 0., 0.],
+      emissive_strength: 1.,
+      ior: 1.5,
+      specular_factor: 1.,
+      specular_color_factor: [1., 1., 1.],
+      sheen_color_factor: [0., 0., 0.],
+      clearcoat_factor: 0.,
+      clearcoat_roughness_factor: 0.,
+      transmission_factor: 0.,
+      alpha_mode: AlphaMode::Opaque,
+      alpha_cutoff: 0.5,
+    }
+  }
+}
+
+impl From<&GltfMaterial> for Principled {
+  fn from(gltf: &GltfMaterial) -> Self {
+    let [r, g, b, alpha] = gltf.base_color_factor;
+    let [er, eg, eb] = gltf.emissive_factor;
+    let [sr, sg, sb] = gltf.specular_color_factor;
+    let [hr, hg, hb] = gltf.sheen_color_factor;
+    let f0 = ((gltf.ior - 1.) / (gltf.ior + 1.)).powi(2);
+    let specular = f0 * gltf.specular_factor * luminance(Color::new(sr, sg, sb)) / 0.08;
+    Principled {
+      base_color: Color::new(r, g, b),
+      metallic: gltf.metallic_factor,
+      roughness: gltf.roughness_factor,
+      specular: specular.clamp(0., 1.),
+      specular_tint: 0.,
+      sheen: luminance(Color::new(hr, hg, hb)).clamp(0., 1.),
+      sheen_tint: 0.,
+      clearcoat: gltf.clearcoat_factor,
+      clearcoat_gloss: 1. - gltf.clearcoat_roughness_factor,
+      transmission: gltf.transmission_factor,
+      ior: gltf.ior,
+      emission: gltf.emissive_strength * Color::new(er, eg, eb),
+      opacity: match gltf.alpha_mode {
+        AlphaMode::Opaque => 1.,
+        AlphaMode::Mask if alpha >= gltf.alpha_cutoff => 1.,
+        AlphaMode::Mask => 0.,
+        AlphaMode::Blend => alpha.clamp(0., 1.),
+      },
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+  Null,
+  Bool(bool),
+  Number(f64),
+  String(String),
+  Array(Vec<Json>),
+  Object(Vec<(String, Json)>),
+}
+
+impl Json {
+  fn get(&self, key: &str) -> Option<&Json> {
+    match self {
+      Json::Object(entries) => entries
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  fn number(&self) -> Option<f64> {
+    match self {
+      Json::Number(value) => Some(*value),
+      _ => None,
+    }
+  }
+
+  fn string(&self) -> Option<&str> {
+    match self {
+      Json::String(value) => Some(value),
+      _ => None,
+    }
+  }
+
+  fn array(&self) -> Option<&[Json]> {
+    match self {
+      Json::Array(values) => Some(values),
+      _ => None,
+    }
+  }
+
+  fn numbers<const N: usize>(&self) -> Option<[f64; N]> {
+    let values = self.array()?;
+    if values.len() != N {
+      return None;
+    }
+    let mut result = [0.; N];
+    for (slot, value) in result.iter_mut().zip(values) {
+      *slot = value.number()?;
+    }
+    Some(result)
+  }
+}
+
+struct Parser<'a> {
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl Parser<'_> {
+  fn invalid(&self, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{message} at byte {}", self.position))
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self
+      .bytes
+      .get(self.position)
+      .is_some_and(u8::is_ascii_whitespace)
+    {
+      self.position += 1;
+    }
+  }
+
+  fn expect(&mut self, byte: u8) -> io::Result<()> {
+    self.skip_whitespace();
+    if self.bytes.get(self.position) != Some(&byte) {
+      return Err(self.invalid(&format!("expected `{}`", byte as char)));
+    }
+    self.position += 1;
+    Ok(())
+  }
+
+  fn literal(&mut self, text: &str, value: Json) -> io::Result<Json> {
+    if !self.bytes[self.position..].starts_with(text.as_bytes()) {
+      return Err(self.invalid("unexpected token"));
+    }
+    self.position += text.len();
+    Ok(value)
+  }
+
+  fn value(&mut self, depth: usize) -> io::Result<Json> {
+    if depth > 128 {
+      return Err(self.invalid("document nested too deeply"));
+    }
+    self.skip_whitespace();
+    match self.bytes.get(self.position) {
+      Some(b'{') => self.object(depth),
+      Some(b'[') => self.array(depth),
+      Some(b'"') => self.string().map(Json::String),
+      Some(b't') => self.literal("true", Json::Bool(true)),
+      Some(b'f') => self.literal("false", Json::Bool(false)),
+      Some(b'n') => self.literal("null", Json::Null),
+      Some(_) => self.number(),
+      None => Err(self.invalid("unexpected end of document")),
+    }
+  }
+
+  fn object(&mut self, depth: usize) -> io::Result<Json> {
+    self.expect(b'{')?;
+    let mut entries = Vec::new();
+    self.skip_whitespace();
+    if self.bytes.get(self.position) == Some(&b'}') {
+      self.position += 1;
+      return Ok(Json::Object(entries));
+    }
+    loop {
+      self.skip_whitespace();
+      let key = self.string()?;
+      self.expect(b':')?;
+      entries.push((key, self.value(depth + 1)?));
+      self.skip_whitespace();
+      match self.bytes.get(self.position) {
+        Some(b',') => self.position += 1,
+        Some(b'}') => {
+          self.position += 1;
+          return Ok(Json::Object(entries));
+        }
+        _ => return Err(self.invalid("expected `,` or `}`")),
+      }
+    }
+  }
+
+  fn array(&mut self, depth: usize) -> io::Result<Json> {
+    self.expect(b'[')?;
+    let mut values = Vec::new();
+    self.skip_whitespace();
+    if self.bytes.get(self.position) == Some(&b']') {
+      self.position += 1;
+      return Ok(Json::Array(values));
+    }
+    loop {
+      values.push(self.value(depth + 1)?);
+      self.skip_whitespace();
+      match self.bytes.get(self.position) {
+        Some(b',') => self.position += 1,
+        Some(b']') => {
+          self.position += 1;
+          return Ok(Json::Array(values));
+        }
+        _ => return Err(self.invalid("expected `,` or `]`")),
+      }
+    }
+  }
+
+  fn string(&mut self) -> io::Result<String> {
+    if self.bytes.get(self.position) != Some(&b'"') {
+      return Err(self.invalid("expected string"));
+    }
+    self.position += 1;
+    let mut text = Vec::new();
+    loop {
+      let byte = *self
+        .bytes
+        .get(self.position)
+        .ok_or_else(|| self.invalid("unterminated string"))?;
+      self.position += 1;
+      match byte {
+        b'"' => break,
+        b'\\' => {
+          let escape = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| self.invalid("unterminated escape"))?;
+          self.position += 1;
+          let decoded = match escape {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+              let digits = self
+                .bytes
+                .get(self.position..self.position + 4)
+                .and_then(|digits| std::str::from_utf8(digits).ok())
+                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| self.invalid("malformed unicode escape"))?;
+              self.position += 4;
+              char::from_u32(digits).unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            _ => return Err(self.invalid("unknown escape")),
+          };
+          let mut buffer = [0; 4];
+          text.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+        }
+        _ => text.push(byte),
+      }
+    }
+    String::from_utf8(text).map_err(|_| self.invalid("string is not UTF-8"))
+  }
+
+  fn number(&mut self) -> io::Result<Json> {
+    let start = self.position;
+    while self
+      .bytes
+      .get(self.position)
+      .is_some_and(|b| b.is_ascii_digit() || b"+-.eE".contains(b))
+    {
+      self.position += 1;
+    }
+    std::str::from_utf8(&self.bytes[start..self.position])
+      .ok()
+      .and_then(|text| text.parse::<f64>().ok())
+      .map(Json::Number)
+      .ok_or_else(|| self.invalid("malformed number"))
+  }
+}
+
+fn json_chunk(bytes: &[u8]) -> io::Result<&[u8]> {
+  if !bytes.starts_with(GLB_MAGIC) {
+    return Ok(bytes);
+  }
+  let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+  let word = |offset: usize| {
+    bytes
+      .get(offset..offset + 4)
+      .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+      .ok_or_else(|| invalid("truncated GLB header"))
+  };
+  let (length, kind) = (word(12)? as usize, word(16)?);
+  if kind != GLB_JSON_CHUNK {
+    return Err(invalid("first GLB chunk is not JSON"));
+  }
+  bytes
+    .get(20..20usize.saturating_add(length))
+    .ok_or_else(|| invalid("truncated GLB JSON chunk"))
+}
+
+impl GltfMaterial {
+  pub fn parse(bytes: &[u8]) -> io::Result<Vec<(String, GltfMaterial)>> {
+    let mut parser = Parser {
+      bytes: json_chunk(bytes)?,
+      position: 0,
+    };
+    let document = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position != parser.bytes.len() {
+      return Err(parser.invalid("trailing data after document"));
+    }
+
+    let materials = match document.get("materials") {
+      Some(materials) => materials
+        .array()
+        .ok_or_else(|| parser.invalid("`materials` is not an array"))?,
+      None => &[],
+    };
+    Ok(
+      materials
+        .iter()
+        .enumerate()
+        .map(|(index, material)| {
+          let name = material
+            .get("name")
+            .and_then(Json::string)
+            .map_or_else(|| format!("material{index}"), str::to_owned);
+          (name, Self::from_json(material))
+        })
+        .collect(),
+    )
+  }
+
+  fn from_json(material: &Json) -> GltfMaterial {
+    let mut gltf = GltfMaterial::default();
+    let number =
+      |value: Option<&Json>, default: f64| value.and_then(Json::number).unwrap_or(default);
+    let extension = |name: &str| material.get("extensions").and_then(|e| e.get(name));
+
+    if let Some(pbr) = material.get("pbrMetallicRoughness") {
+      gltf.base_color_factor = pbr
+        .get("baseColorFactor")
+        .and_then(Json::numbers)
+        .unwrap_or(gltf.base_color_factor);
+      gltf.metallic_factor = number(pbr.get("metallicFactor"), gltf.metallic_factor);
+      gltf.roughness_factor = number(pbr.get("roughnessFactor"), gltf.roughness_factor);
+    }
+    gltf.emissive_factor = material
+      .get("emissiveFactor")
+      .and_then(Json::numbers)
+      .unwrap_or(gltf.emissive_factor);
+    gltf.alpha_mode = match material.get("alphaMode").and_then(Json::string) {
+      Some("MASK") => AlphaMode::Mask,
+      Some("BLEND") => AlphaMode::Blend,
+      _ => AlphaMode::Opaque,
+    };
+    gltf.alpha_cutoff = number(material.get("alphaCutoff"), gltf.alpha_cutoff);
+
+    if let Some(strength) = extension("KHR_materials_emissive_strength") {
+      gltf.emissive_strength = number(strength.get("emissiveStrength"), gltf.emissive_strength);
+    }
+    if let Some(ior) = extension("KHR_materials_ior") {
+      gltf.ior = number(ior.get("ior"), gltf.ior);
+    }
+    if let Some(specular) = extension("KHR_materials_specular") {
+      gltf.specular_factor = number(specular.get("specularFactor"), gltf.specular_factor);
+      gltf.specular_color_factor = specular
+        .get("specularColorFactor")
+        .and_then(Json::numbers)
+        .unwrap_or(gltf.specular_color_factor);
+    }
+    if let Some(sheen) = extension("KHR_materials_sheen") {
+      gltf.sheen_color_factor = sheen
+        .get("sheenColorFactor")
+        .and_then(Json::numbers)
+        .unwrap_or(gltf.sheen_color_factor);
+    }
+    if let Some(clearcoat) = extension("KHR_materials_clearcoat") {
+      gltf.clearcoat_factor = number(clearcoat.get("clearcoatFactor"), gltf.clearcoat_factor);
+      gltf.clearcoat_roughness_factor =
+        number(clearcoat.get("clearcoatRoughnessFactor"), gltf.clearcoat_roughness_factor);
+    }
+    if let Some(transmission) = extension("KHR_materials_transmission") {
+      gltf.transmission_factor =
+        number(transmission.get("transmissionFactor"), gltf.transmission_factor);
+    }
+    gltf
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DOCUMENT: &str = r#"{
+    "asset": { "version": "2.0" },
+    "materials": [
+      {
+        "name": "leaf",
+        "pbrMetallicRoughness": {
+          "baseColorFactor": [0.2, 0.6, 0.1, 0.25],
+          "metallicFactor": 0.0,
+          "roughnessFactor": 0.7
+        },
+        "alphaMode": "BLEND"
+      },
+      {
+        "emissiveFactor": [1, 0.5, 0],
+        "extensions": {
+          "KHR_materials_emissive_strength": { "emissiveStrength": 4 },
+          "KHR_materials_ior": { "ior": 1.33 },
+          "KHR_materials_transmission": { "transmissionFactor": 1e0 }
+        }
+      }
+    ]
+  }"#;
+
+  #[test]
+  fn parses_pbr_materials_and_extensions() {
+    let materials = GltfMaterial::parse(DOCUMENT.as_bytes()).unwrap();
+    assert_eq!(materials.len(), 2);
+    let (name, leaf) = &materials[0];
+    assert_eq!(name, "leaf");
+    assert_eq!(leaf.base_color_factor, [0.2, 0.6, 0.1, 0.25]);
+    assert_eq!(leaf.roughness_factor, 0.7);
+    let leaf = Principled::from(leaf);
+    assert_eq!(leaf.opacity, 0.25);
+    assert_eq!(leaf.transmission, 0.);
+
+    let (name, water) = &materials[1];
+    assert_eq!(name, "material1");
+    assert_eq!(water.emissive_strength, 4.);
+    assert_eq!(water.ior, 1.33);
+    assert_eq!(water.transmission_factor, 1.);
+    assert_eq!(Principled::from(water).emission.x, 4.);
+  }
+
+  #[test]
+  fn reads_json_chunk_from_glb() {
+    let json = br#"{"materials":[{"name":"a"}]}"#;
+    let mut glb = Vec::new();
+    glb.extend_from_slice(GLB_MAGIC);
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((20 + json.len()) as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_JSON_CHUNK.to_le_bytes());
+    glb.extend_from_slice(json);
+    let materials = GltfMaterial::parse(&glb).unwrap();
+    assert_eq!(materials[0].0, "a");
+  }
+
+  #[test]
+  fn rejects_malformed_documents() {
+    for source in [
+      &b"{"[..],
+      b"{\"materials\": 3}",
+      b"[1, 2",
+      b"{} trailing",
+      b"glTF",
+    ] {
+      let error = GltfMaterial::parse(source).unwrap_err();
+      assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+  }
+}
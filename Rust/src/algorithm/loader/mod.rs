@@ -0,0 +1,3 @@
+mod gltf;
+
+pub use gltf::{AlphaMode, GltfMaterial};
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::Texture;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Material, ScatterSample};
+
+const DELTA: f64 = 1e-3;
+
+pub struct BumpMap {
+  pub base: Arc<dyn Material>,
+  pub height: Arc<dyn Texture>,
+  pub scale: f64,
+}
+
+impl BumpMap {
+  pub fn new(base: Arc<dyn Material>, height: Arc<dyn Texture>, scale: f64) -> BumpMap {
+    BumpMap {
+      base,
+      height,
+      scale,
+    }
+  }
+
+  fn shade<'a>(&self, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+    let HitRecord {
+      u,
+      v,
+      point,
+      tangent,
+      bitangent,
+      ..
+    } = *hit_record;
+    let height = |u: f64, v: f64, offset| luminance(self.height.value(u, v, point + offset));
+    let center = height(u, v, Vec3::zero());
+    let dh_du = (height(u + DELTA, v, DELTA * tangent) - center) / DELTA;
+    let dh_dv = (height(u, v + DELTA, DELTA * bitangent) - center) / DELTA;
+    let gradient = self.scale * (dh_du * tangent + dh_dv * bitangent);
+    let mut shaded = *hit_record;
+    shaded.set_shading_normal(if hit_record.front_face {
+      hit_record.normal - gradient
+    } else {
+      hit_record.normal + gradient
+    });
+    shaded
+  }
+}
+
+impl Material for BumpMap {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    self.base.scatter(ray_in, &self.shade(hit_record))
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    self.base.sample(ray_in, &self.shade(hit_record))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    self.base.eval(ray_in, &self.shade(hit_record), direction)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    self.base.pdf(ray_in, &self.shade(hit_record), direction)
+  }
+
+  fn is_specular(&self, hit_record: &HitRecord) -> bool {
+    self.base.is_specular(hit_record)
+  }
+
+  fn opacity(&self, hit_record: &HitRecord) -> f64 {
+    self.base.opacity(hit_record)
+  }
+
+  fn emitted(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    self.base.emitted(ray_in, hit_record)
+  }
+
+  fn emission(&self) -> Color {
+    self.base.emission()
+  }
+}
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::random;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::microfacet::{TrowbridgeReitz, fresnel_dielectric, reflect};
+use super::{Material, ScatterSample};
+
+pub struct Coated {
+  pub base: Arc<dyn Material>,
+  pub refraction_index: f64,
+  pub distribution: TrowbridgeReitz,
+  pub absorption: Color,
+  pub thickness: f64,
+}
+
+impl Coated {
+  pub fn new(base: Arc<dyn Material>, refraction_index: f64) -> Coated {
+    Self::rough(base, refraction_index, 0.)
+  }
+
+  pub fn rough(base: Arc<dyn Material>, refraction_index: f64, roughness: f64) -> Coated {
+    Self::absorbing(base, refraction_index, roughness, Color::zero(), 0.)
+  }
+
+  pub fn absorbing(
+    base: Arc<dyn Material>,
+    refraction_index: f64,
+    roughness: f64,
+    absorption: Color,
+    thickness: f64,
+  ) -> Coated {
+    Coated {
+      base,
+      refraction_index,
+      distribution: TrowbridgeReitz::from_roughness(roughness, roughness),
+      absorption,
+      thickness,
+    }
+  }
+
+  fn refracted_cosine(&self, cos_theta: f64) -> f64 {
+    let sin2_theta = (1. - cos_theta * cos_theta) / (self.refraction_index * self.refraction_index);
+    (1. - sin2_theta).max(1e-4).sqrt()
+  }
+
+  fn layer_transmittance(&self, wo: Vec3, wi: Vec3) -> Color {
+    let mut path = 1. / self.refracted_cosine(wo.z);
+    if wi.z > 0. {
+      path += 1. / self.refracted_cosine(wi.z);
+    }
+    (-self.thickness * path * self.absorption).exp()
+  }
+}
+
+impl Material for Coated {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    self
+      .sample(ray_in, hit_record)
+      .map(|sample| (sample.ray, sample.attenuation))
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    if !hit_record.front_face {
+      return self.base.sample(ray_in, hit_record);
+    }
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let fresnel = fresnel_dielectric(wo.z, self.refraction_index);
+
+    let mut rng = random::rng();
+    if rng.random::<f64>() < fresnel {
+      let smooth = self.distribution.effectively_smooth();
+      let (wi, weight) = if smooth {
+        (Vec3::new(-wo.x, -wo.y, wo.z), 1.)
+      } else {
+        let wm = self
+          .distribution
+          .sample_wm(wo, (rng.random::<f64>(), rng.random::<f64>()));
+        let wi = reflect(wo, wm);
+        let masking = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+        (wi, masking * fresnel_dielectric(wo.dot(wm), self.refraction_index) / fresnel)
+      };
+      if wi.z <= 0. {
+        return None;
+      }
+      return Some(ScatterSample {
+        ray: Ray::new(hit_record.point, frame.transform(wi)),
+        attenuation: Color::new(weight, weight, weight),
+        specular: smooth,
+      });
+    }
+
+    let sample = self.base.sample(ray_in, hit_record)?;
+    let wi = frame.to_local(sample.ray.direction.normalization());
+    let exit = if wi.z > 0. {
+      1. - fresnel_dielectric(wi.z, self.refraction_index)
+    } else {
+      1.
+    };
+    Some(ScatterSample {
+      attenuation: exit * self.layer_transmittance(wo, wi) * sample.attenuation,
+      ..sample
+    })
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    if !hit_record.front_face {
+      return self.base.eval(ray_in, hit_record, direction);
+    }
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    if wo.z <= 0. || wi.z <= 0. {
+      return Color::zero();
+    }
+    let transmission = (1. - fresnel_dielectric(wo.z, self.refraction_index))
+      * (1. - fresnel_dielectric(wi.z, self.refraction_index));
+    let base = transmission
+      * self.layer_transmittance(wo, wi)
+      * self.base.eval(ray_in, hit_record, direction);
+    if self.distribution.effectively_smooth() {
+      return base;
+    }
+    let wm = (wo + wi).normalization();
+    let coat = self.distribution.d(wm)
+      * self.distribution.g(wo, wi)
+      * fresnel_dielectric(wo.dot(wm), self.refraction_index)
+      / (4. * wo.z);
+    Color::new(coat, coat, coat) + base
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    if !hit_record.front_face {
+      return self.base.pdf(ray_in, hit_record, direction);
+    }
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    if wo.z <= 0. || wi.z <= 0. {
+      return 0.;
+    }
+    let fresnel = fresnel_dielectric(wo.z, self.refraction_index);
+    let base = (1. - fresnel) * self.base.pdf(ray_in, hit_record, direction);
+    if self.distribution.effectively_smooth() {
+      return base;
+    }
+    let wm = (wo + wi).normalization();
+    fresnel * self.distribution.pdf(wo, wm) / (4. * wo.dot(wm)) + base
+  }
+
+  fn is_specular(&self, hit_record: &HitRecord) -> bool {
+    self.base.is_specular(hit_record)
+  }
+
+  fn emitted(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    self.base.emitted(ray_in, hit_record)
+  }
+
+  fn emission(&self) -> Color {
+    self.base.emission()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::f64::consts::PI;
+
+  use super::*;
+  use crate::algorithm::camera::Camera;
+  use crate::algorithm::color::luminance;
+  use crate::algorithm::hittable_list::HittableList;
+  use crate::algorithm::light::{LightList, PointLight};
+  use crate::algorithm::material::Lambertian;
+  use crate::algorithm::quad::Quad;
+
+  fn varnished() -> Coated {
+    Coated::new(Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8))), 1.5)
+  }
+
+  #[test]
+  fn smooth_coat_is_a_delta_event_over_the_base() {
+    let coated = varnished();
+    let ray = Ray::new(Vec3::new(-0.5, 0., 1.), Vec3::new(0.5, 0., -1.));
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &coated);
+    assert!(!coated.is_specular(&record));
+
+    let samples = 200000;
+    let mut specular = 0;
+    let mut diffuse = 0.;
+    for _ in 0..samples {
+      let Some(sample) = coated.sample(&ray, &record) else {
+        continue;
+      };
+      if sample.specular {
+        specular += 1;
+        assert!((sample.attenuation - Color::one()).near_zero());
+      } else {
+        diffuse += luminance(sample.attenuation);
+      }
+    }
+    let wo = Vec3::new(0.5, 0., 1.).normalization();
+    let fresnel = fresnel_dielectric(wo.z, 1.5);
+    assert!((specular as f64 / samples as f64 - fresnel).abs() < 0.005);
+    let evaluated = (0..samples)
+      .map(|_| 4. * PI * luminance(coated.eval(&ray, &record, Vec3::random_unit_vector())))
+      .sum::<f64>()
+      / samples as f64;
+    let diffuse = diffuse / samples as f64;
+    assert!((diffuse - evaluated).abs() < 0.02 * diffuse, "{diffuse} {evaluated}");
+  }
+
+  #[test]
+  fn point_light_reaches_a_smooth_coated_plane() {
+    let mut world = HittableList::default();
+    world.add(Quad::new(
+      Vec3::new(-2., -2., -1.),
+      Vec3::new(4., 0., 0.),
+      Vec3::new(0., 4., 0.),
+      Arc::new(varnished()),
+    ));
+    let mut lights = LightList::default();
+    lights.add(PointLight::new(Vec3::new(0.5, 0.5, 0.), Color::new(2., 2., 2.)));
+    let mut camera =
+      Camera::new(8, 8, 4, 4, Vec3::zero(), Vec3::new(0., 0., -1.), Vec3::new(0., 1., 0.));
+    camera.background = Arc::new(Color::zero());
+    camera.seed = Some(3);
+    let image = camera.render(&world, &lights);
+    assert!(
+      image
+        .chunks(4)
+        .all(|pixel| pixel[..3].iter().all(|&channel| channel > 0))
+    );
+  }
+}
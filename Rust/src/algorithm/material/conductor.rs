@@ -0,0 +1,217 @@
+use rand::Rng;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::complex::Complex;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::random;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::spectrum::rgb_to_spectrum;
+use crate::algorithm::vec3::Vec3;
+
+use super::Material;
+use super::microfacet::{TrowbridgeReitz, fresnel_complex, reflect};
+use super::thin_film::{ThinFilm, film_wavelengths};
+
+pub struct Conductor {
+  pub eta: Color,
+  pub k: Color,
+  pub distribution: TrowbridgeReitz,
+  pub thin_film: Option<ThinFilm>,
+}
+
+impl Conductor {
+  pub fn new(eta: Color, k: Color, roughness: f64) -> Conductor {
+    Self::anisotropic(eta, k, roughness, roughness)
+  }
+
+  pub fn anisotropic(eta: Color, k: Color, roughness_u: f64, roughness_v: f64) -> Conductor {
+    Conductor {
+      eta,
+      k,
+      distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+      thin_film: None,
+    }
+  }
+
+  pub fn gold(roughness: f64) -> Conductor {
+    Self::new(Color::new(0.143, 0.374, 1.442), Color::new(3.983, 2.385, 1.603), roughness)
+  }
+
+  pub fn copper(roughness: f64) -> Conductor {
+    Self::new(Color::new(0.200, 0.924, 1.102), Color::new(3.912, 2.452, 2.142), roughness)
+  }
+
+  pub fn aluminium(roughness: f64) -> Conductor {
+    Self::new(Color::new(1.657, 0.880, 0.521), Color::new(9.224, 6.270, 4.837), roughness)
+  }
+
+  pub fn silver(roughness: f64) -> Conductor {
+    Self::new(Color::new(0.155, 0.117, 0.138), Color::new(4.828, 3.122, 2.147), roughness)
+  }
+
+  pub fn fresnel(&self, cos_theta: f64) -> Color {
+    Color::new(
+      fresnel_complex(cos_theta, Complex::new(self.eta.x, self.k.x)),
+      fresnel_complex(cos_theta, Complex::new(self.eta.y, self.k.y)),
+      fresnel_complex(cos_theta, Complex::new(self.eta.z, self.k.z)),
+    )
+  }
+
+  fn reflectance(&self, cos_theta: f64, ray_in: &Ray) -> Color {
+    let Some(thin_film) = &self.thin_film else {
+      return self.fresnel(cos_theta);
+    };
+    let (lambdas, _) = film_wavelengths(ray_in);
+    let substrate = match ray_in.wavelengths {
+      Some(wavelengths) => {
+        let eta = rgb_to_spectrum(self.eta, wavelengths.hero());
+        let k = rgb_to_spectrum(self.k, wavelengths.hero());
+        [Complex::new(eta, k); 3]
+      }
+      None => [
+        Complex::new(self.eta.x, self.k.x),
+        Complex::new(self.eta.y, self.k.y),
+        Complex::new(self.eta.z, self.k.z),
+      ],
+    };
+    let [r, g, b] =
+      [0, 1, 2].map(|c| thin_film.reflectance(cos_theta, 1., substrate[c], lambdas[c]));
+    Color::new(r, g, b)
+  }
+
+  fn scattered(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Ray {
+    let wavelengths = match self.thin_film {
+      Some(_) => film_wavelengths(ray_in).1,
+      None => ray_in.wavelengths,
+    };
+    Ray {
+      wavelengths,
+      ..Ray::new(hit_record.point, direction)
+    }
+  }
+}
+
+impl Material for Conductor {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    if wo.z <= 0. {
+      return None;
+    }
+
+    if self.distribution.effectively_smooth() {
+      let wi = Vec3::new(-wo.x, -wo.y, wo.z);
+      let scattered = self.scattered(ray_in, hit_record, frame.transform(wi));
+      return Some((scattered, self.reflectance(wo.z, ray_in)));
+    }
+
+    let mut rng = random::rng();
+    let wm = self
+      .distribution
+      .sample_wm(wo, (rng.random::<f64>(), rng.random::<f64>()));
+    let wi = reflect(wo, wm);
+    if wi.z <= 0. {
+      return None;
+    }
+    let weight = self.distribution.g(wo, wi) / self.distribution.g1(wo);
+    let scattered = self.scattered(ray_in, hit_record, frame.transform(wi));
+    Some((scattered, weight * self.reflectance(wo.dot(wm), ray_in)))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    if self.distribution.effectively_smooth() || wo.z <= 0. || wi.z <= 0. {
+      return Color::zero();
+    }
+    let wm = (wo + wi).normalization();
+    let f = self.distribution.d(wm) * self.distribution.g(wo, wi) / (4. * wo.z);
+    f * self.reflectance(wo.dot(wm), ray_in)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    if self.distribution.effectively_smooth() || wo.z <= 0. || wi.z <= 0. {
+      return 0.;
+    }
+    let wm = (wo + wi).normalization();
+    self.distribution.pdf(wo, wm) / (4. * wo.dot(wm))
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    self.distribution.effectively_smooth()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::f64::consts::PI;
+
+  use super::*;
+  use crate::algorithm::color::luminance;
+
+  fn integrate_hemisphere(f: impl Fn(Vec3) -> f64) -> f64 {
+    let (theta_steps, phi_steps) = (400, 400);
+    let d_theta = 0.5 * PI / theta_steps as f64;
+    let d_phi = 2. * PI / phi_steps as f64;
+    let mut total = 0.;
+    for i in 0..theta_steps {
+      let theta = (i as f64 + 0.5) * d_theta;
+      for j in 0..phi_steps {
+        let phi = (j as f64 + 0.5) * d_phi;
+        let w = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+        total += f(w) * theta.sin();
+      }
+    }
+    total * d_theta * d_phi
+  }
+
+  fn incoming() -> Ray {
+    Ray::new(Vec3::new(-0.5, 0., 1.), Vec3::new(0.5, 0., -1.))
+  }
+
+  #[test]
+  fn sampled_weights_match_eval_over_pdf() {
+    let conductor = Conductor::gold(0.4);
+    let ray = incoming();
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &conductor);
+    let samples = 100000;
+    let mut reflected = 0;
+    for _ in 0..samples {
+      let Some((scattered, attenuation)) = conductor.scatter(&ray, &record) else {
+        continue;
+      };
+      reflected += 1;
+      let pdf = conductor.pdf(&ray, &record, scattered.direction);
+      let expected = conductor.eval(&ray, &record, scattered.direction) / pdf;
+      assert!((attenuation - expected).len() < 1e-9 * attenuation.len().max(1.));
+    }
+    let integral = integrate_hemisphere(|wi| conductor.pdf(&ray, &record, wi));
+    assert!(integral <= 1.01);
+    assert!((integral - reflected as f64 / samples as f64).abs() < 0.01);
+  }
+
+  #[test]
+  fn lossless_conductor_passes_the_white_furnace() {
+    let ray = incoming();
+    for roughness in [0.2, 0.5, 1.] {
+      let conductor = Conductor::new(Color::new(1e4, 1e4, 1e4), Color::zero(), roughness);
+      let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &conductor);
+      let samples = 100000;
+      let sampled = (0..samples)
+        .filter_map(|_| conductor.scatter(&ray, &record))
+        .map(|(_, attenuation)| luminance(attenuation))
+        .sum::<f64>()
+        / samples as f64;
+      let evaluated = integrate_hemisphere(|wi| luminance(conductor.eval(&ray, &record, wi)));
+      assert!(sampled <= 1. && evaluated <= 1.01, "{roughness}: {sampled} {evaluated}");
+      assert!((sampled - evaluated).abs() < 0.01, "{roughness}: {sampled} {evaluated}");
+      if roughness <= 0.2 {
+        assert!(sampled > 0.97, "{sampled}");
+      }
+    }
+  }
+}
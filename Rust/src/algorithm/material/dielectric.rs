@@ -1,17 +1,92 @@
 use super::Material;
+use super::thin_film::{ThinFilm, film_wavelengths};
 use crate::algorithm::color::Color;
 use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::random;
 use crate::algorithm::ray::Ray;
 use rand::Rng;
 
+#[derive(Debug, Clone, Copy)]
+pub enum Dispersion {
+  Cauchy { a: f64, b: f64 },
+  Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+  pub fn bk7() -> Dispersion {
+    Dispersion::Sellmeier {
+      b: [1.03961212, 0.231792344, 1.01046945],
+      c: [0.00600069867, 0.0200179144, 103.560653],
+    }
+  }
+
+  pub fn diamond() -> Dispersion {
+    Dispersion::Sellmeier {
+      b: [0.3306, 4.3356, 0.],
+      c: [0.030625, 0.011236, 0.],
+    }
+  }
+
+  pub fn refraction_index(&self, lambda: f64) -> f64 {
+    let micrometers = lambda / 1000.;
+    let l2 = micrometers * micrometers;
+    match self {
+      Dispersion::Cauchy { a, b } => a + b / l2,
+      Dispersion::Sellmeier { b, c } => {
+        let n2 = 1. + b.iter().zip(c).map(|(b, c)| b * l2 / (l2 - c)).sum::<f64>();
+        n2.sqrt()
+      }
+    }
+  }
+}
+
 #[derive(Default)]
 pub struct Dielectric {
   refraction_index: f64,
+  absorption: Color,
+  dispersion: Option<Dispersion>,
+  thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
   pub fn new(refraction_index: f64) -> Dielectric {
-    Dielectric { refraction_index }
+    Dielectric {
+      refraction_index,
+      ..Dielectric::default()
+    }
+  }
+
+  pub fn with_absorption(refraction_index: f64, absorption: Color) -> Option<Dielectric> {
+    let valid = |c: f64| c.is_finite() && c >= 0.;
+    (valid(absorption.x) && valid(absorption.y) && valid(absorption.z)).then_some(Dielectric {
+      absorption,
+      ..Self::new(refraction_index)
+    })
+  }
+
+  pub fn with_thin_film(refraction_index: f64, thin_film: ThinFilm) -> Dielectric {
+    Dielectric {
+      thin_film: Some(thin_film),
+      ..Self::new(refraction_index)
+    }
+  }
+
+  pub fn dispersive(dispersion: Dispersion) -> Dielectric {
+    Dielectric {
+      refraction_index: dispersion.refraction_index(550.),
+      absorption: Color::zero(),
+      dispersion: Some(dispersion),
+      thin_film: None,
+    }
+  }
+
+  pub fn tinted(refraction_index: f64, color: Color, distance: f64) -> Option<Dielectric> {
+    if !(distance.is_finite() && distance > 0.) {
+      return None;
+    }
+    let absorbance = |c: f64| -c.clamp(1e-6, 1.).ln() / distance;
+    let absorption = Color::new(absorbance(color.x), absorbance(color.y), absorbance(color.z));
+    Self::with_absorption(refraction_index, absorption)
   }
 
   pub fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
@@ -19,15 +94,68 @@ impl Dielectric {
     r0 = r0 * r0;
     r0 + (1. - r0) * (1. - cosine).powi(5)
   }
+
+  fn transmittance(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    if hit_record.front_face {
+      Color::new(1., 1., 1.)
+    } else {
+      let distance = hit_record.t * ray_in.direction.len();
+      (-distance * self.absorption).exp()
+    }
+  }
+
+  fn scatter_thin_film(
+    &self,
+    thin_film: &ThinFilm,
+    ray_in: &Ray,
+    hit_record: &HitRecord,
+  ) -> Option<(Ray, Color)> {
+    let mut rng = random::rng();
+    let (lambdas, wavelengths) = film_wavelengths(ray_in);
+    let (outer, substrate, ri) = if hit_record.front_face {
+      (1., self.refraction_index, 1. / self.refraction_index)
+    } else {
+      (self.refraction_index, 1., self.refraction_index)
+    };
+    let unit_direction = ray_in.direction.normalization();
+    let cos_theta = (-unit_direction).dot(hit_record.normal).min(1.);
+    let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+    let [r, g, b] =
+      lambdas.map(|lambda| thin_film.reflectance(cos_theta, outer, substrate.into(), lambda));
+    let reflectance = Color::new(r, g, b);
+    let probability = (r + g + b) / 3.;
+    let (direction, attenuation) = if ri * sin_theta > 1.0 {
+      (unit_direction.reflect(hit_record.normal), Color::one())
+    } else if probability > rng.random::<f64>() {
+      (unit_direction.reflect(hit_record.normal), reflectance / probability)
+    } else {
+      let transmitted = Color::one() - reflectance;
+      (unit_direction.refract(hit_record.normal, ri), transmitted / (1. - probability))
+    };
+    let scattered = Ray {
+      wavelengths: wavelengths.or(ray_in.wavelengths),
+      ..Ray::new(hit_record.point, direction)
+    };
+    Some((scattered, attenuation * self.transmittance(ray_in, hit_record)))
+  }
 }
 
 impl Material for Dielectric {
   fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
-    let mut rng = rand::rng();
+    if let Some(thin_film) = &self.thin_film {
+      return self.scatter_thin_film(thin_film, ray_in, hit_record);
+    }
+    let mut rng = random::rng();
+    let (refraction_index, wavelengths) = match (self.dispersion, ray_in.wavelengths) {
+      (Some(dispersion), Some(wavelengths)) => {
+        (dispersion.refraction_index(wavelengths.hero()), Some(wavelengths.terminate_secondary()))
+      }
+      _ => (self.refraction_index, ray_in.wavelengths),
+    };
     let ri = if hit_record.front_face {
-      1. / self.refraction_index
+      1. / refraction_index
     } else {
-      self.refraction_index
+      refraction_index
     };
     let unit_direction = ray_in.direction.normalization();
     let cos_theta = (-unit_direction).dot(hit_record.normal).min(1.);
@@ -38,6 +166,63 @@ impl Material for Dielectric {
       } else {
         unit_direction.refract(hit_record.normal, ri)
       };
-    Some((Ray::new(hit_record.point, direction), Color::new(1., 1., 1.)))
+    let scattered = Ray {
+      wavelengths,
+      ..Ray::new(hit_record.point, direction)
+    };
+    Some((scattered, self.transmittance(ray_in, hit_record)))
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    true
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::vec3::Vec3;
+
+  #[test]
+  fn dispersion_models_match_bk7_at_the_helium_d_line() {
+    let cauchy = Dispersion::Cauchy {
+      a: 1.5046,
+      b: 0.0042,
+    };
+    for dispersion in [Dispersion::bk7(), cauchy] {
+      let n = dispersion.refraction_index(587.6);
+      assert!((n - 1.5168).abs() < 2e-4, "{dispersion:?}: {n}");
+    }
+    assert!(Dispersion::bk7().refraction_index(450.) > Dispersion::bk7().refraction_index(650.));
+  }
+
+  #[test]
+  fn tinted_glass_follows_beer_lambert() {
+    let color = Color::new(0.5, 0.25, 1.);
+    let glass = Dielectric::tinted(1.5, color, 2.).unwrap();
+    let transmittance = |thickness: f64| {
+      let ray = Ray::new(Vec3::zero(), Vec3::new(0., 0., 1.));
+      let record =
+        HitRecord::new(Vec3::new(0., 0., thickness), thickness, Vec3::new(0., 0., 1.), ray, &glass);
+      assert!(!record.front_face);
+      glass.scatter(&ray, &record).unwrap().1
+    };
+    assert!((transmittance(2.) - color).len() < 1e-12);
+    let half = transmittance(1.);
+    assert!((half * half - color).len() < 1e-12);
+    assert!((transmittance(4.) - color * color).len() < 1e-12);
+  }
+
+  #[test]
+  fn absorption_must_be_finite_and_non_negative() {
+    let tint = Color::new(0.5, 0.5, 0.5);
+    for distance in [0., -1., f64::NAN, f64::INFINITY] {
+      assert!(Dielectric::tinted(1.5, tint, distance).is_none());
+    }
+    assert!(Dielectric::tinted(1.5, Color::new(f64::NAN, 0.5, 0.5), 1.).is_none());
+    for absorption in [-1., f64::NAN, f64::INFINITY] {
+      assert!(Dielectric::with_absorption(1.5, Color::new(0., absorption, 0.)).is_none());
+    }
+    assert!(Dielectric::with_absorption(1.5, Color::zero()).is_some());
   }
 }
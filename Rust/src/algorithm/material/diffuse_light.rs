@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::{self, SolidColor, Texture};
+
+use super::Material;
+
+pub struct DiffuseLight {
+  pub emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+  pub fn new(emit: Color) -> DiffuseLight {
+    Self::with_texture(Arc::new(SolidColor::new(emit)))
+  }
+
+  pub fn with_texture(emit: Arc<dyn Texture>) -> DiffuseLight {
+    DiffuseLight { emit }
+  }
+}
+
+impl Material for DiffuseLight {
+  fn scatter(&self, _: &Ray, _: &HitRecord) -> Option<(Ray, Color)> {
+    None
+  }
+
+  fn emitted(&self, _: &Ray, hit_record: &HitRecord) -> Color {
+    if !hit_record.front_face {
+      return Color::zero();
+    }
+    self
+      .emit
+      .value(hit_record.u, hit_record.v, hit_record.point)
+  }
+
+  fn emission(&self) -> Color {
+    texture::average(self.emit.as_ref())
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    true
+  }
+}
@@ -0,0 +1,45 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::{SolidColor, Texture};
+use crate::algorithm::vec3::Vec3;
+
+use super::Material;
+
+pub struct Isotropic {
+  pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+  pub fn new(albedo: Color) -> Isotropic {
+    Self::with_texture(Arc::new(SolidColor::new(albedo)))
+  }
+
+  pub fn with_texture(albedo: Arc<dyn Texture>) -> Isotropic {
+    Isotropic { albedo }
+  }
+}
+
+impl Material for Isotropic {
+  fn scatter(&self, _: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    let scattered = Ray::new(hit_record.point, Vec3::random_unit_vector());
+    let attenuation = self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    Some((scattered, attenuation))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let albedo = self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    self.pdf(ray_in, hit_record, direction) * albedo
+  }
+
+  fn pdf(&self, _: &Ray, _: &HitRecord, _: Vec3) -> f64 {
+    1. / (4. * PI)
+  }
+}
@@ -1,19 +1,38 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
 use crate::algorithm::color::Color;
 use crate::algorithm::hittable::HitRecord;
 use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::{SolidColor, Texture};
 use crate::algorithm::vec3::Vec3;
 
 use super::Material;
 
-#[derive(Default)]
 pub struct Lambertian {
-  pub albedo: Color,
+  pub albedo: Arc<dyn Texture>,
+}
+
+impl Default for Lambertian {
+  fn default() -> Self {
+    Self::new(Color::zero())
+  }
 }
 
 impl Lambertian {
   pub fn new(albedo: Color) -> Lambertian {
+    Self::with_texture(Arc::new(SolidColor::new(albedo)))
+  }
+
+  pub fn with_texture(albedo: Arc<dyn Texture>) -> Lambertian {
     Lambertian { albedo }
   }
+
+  fn albedo(&self, hit_record: &HitRecord) -> Color {
+    self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point)
+  }
 }
 
 impl Material for Lambertian {
@@ -23,6 +42,34 @@ impl Material for Lambertian {
       scatter_direction = hit_record.normal;
     }
     let scattered = Ray::new(hit_record.point, scatter_direction);
-    Some((scattered, self.albedo))
+    Some((scattered, self.albedo(hit_record)))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    self.pdf(ray_in, hit_record, direction) * self.albedo(hit_record)
+  }
+
+  fn pdf(&self, _: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    hit_record.normal.dot(direction.normalization()).max(0.) / PI
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::texture::CheckerTexture;
+
+  #[test]
+  fn albedo_follows_the_texture() {
+    let checker = CheckerTexture::from_colors(1., Color::one(), Color::new(0.2, 0.4, 0.6));
+    let material = Lambertian::with_texture(Arc::new(checker));
+    let albedo = |x: f64| {
+      let ray = Ray::new(Vec3::new(x, 0.5, 1.), Vec3::new(0., 0., -1.));
+      let record =
+        HitRecord::new(Vec3::new(x, 0.5, 0.5), 0.5, Vec3::new(0., 0., 1.), ray, &material);
+      material.scatter(&ray, &record).unwrap().1
+    };
+    assert!((albedo(0.5) - Color::one()).near_zero());
+    assert!((albedo(1.5) - Color::new(0.2, 0.4, 0.6)).near_zero());
   }
 }
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::loader::GltfMaterial;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Material, Principled, ScatterSample};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateMaterial {
+  pub name: String,
+}
+
+impl fmt::Display for DuplicateMaterial {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "material `{}` is already defined", self.name)
+  }
+}
+
+impl Error for DuplicateMaterial {}
+
+pub struct SharedMaterial {
+  current: AtomicPtr<Arc<dyn Material>>,
+  #[allow(clippy::vec_box)]
+  retired: Mutex<Vec<Box<Arc<dyn Material>>>>,
+}
+
+impl SharedMaterial {
+  pub fn new(material: Arc<dyn Material>) -> SharedMaterial {
+    SharedMaterial {
+      current: AtomicPtr::new(Box::into_raw(Box::new(material))),
+      retired: Mutex::default(),
+    }
+  }
+
+  pub fn get(&self) -> Arc<dyn Material> {
+    self.current().clone()
+  }
+
+  fn current(&self) -> &Arc<dyn Material> {
+    // SAFETY: `current` always holds a pointer from `Box::into_raw`. Replaced boxes are parked
+    // in `retired` rather than freed, so a reference stays valid for as long as `self` does.
+    unsafe { &*self.current.load(Ordering::Acquire) }
+  }
+
+  pub fn set(&self, material: Arc<dyn Material>) -> Arc<dyn Material> {
+    let mut retired = self.retired.lock().unwrap_or_else(PoisonError::into_inner);
+    let previous = self
+      .current
+      .swap(Box::into_raw(Box::new(material)), Ordering::AcqRel);
+    // SAFETY: `previous` came from `Box::into_raw` and is no longer reachable through `current`.
+    let previous = unsafe { Box::from_raw(previous) };
+    let material = Arc::clone(&previous);
+    retired.push(previous);
+    material
+  }
+}
+
+impl Drop for SharedMaterial {
+  fn drop(&mut self) {
+    // SAFETY: `current` holds a pointer from `Box::into_raw` and nothing can borrow it anymore.
+    drop(unsafe { Box::from_raw(*self.current.get_mut()) });
+  }
+}
+
+impl Material for SharedMaterial {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    self.current().scatter(ray_in, hit_record)
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    self.current().sample(ray_in, hit_record)
+  }
+
+  fn emitted(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    self.current().emitted(ray_in, hit_record)
+  }
+
+  fn emission(&self) -> Color {
+    self.current().emission()
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    self.current().eval(ray_in, hit_record, direction)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    self.current().pdf(ray_in, hit_record, direction)
+  }
+
+  fn is_specular(&self, hit_record: &HitRecord) -> bool {
+    self.current().is_specular(hit_record)
+  }
+
+  fn opacity(&self, hit_record: &HitRecord) -> f64 {
+    self.current().opacity(hit_record)
+  }
+}
+
+#[derive(Default, Clone)]
+pub struct MaterialLibrary {
+  materials: HashMap<String, Arc<SharedMaterial>>,
+}
+
+impl MaterialLibrary {
+  pub fn new() -> Self {
+    MaterialLibrary {
+      materials: HashMap::new(),
+    }
+  }
+
+  pub fn add<M>(&mut self, name: &str, material: M) -> Result<Arc<dyn Material>, DuplicateMaterial>
+  where
+    M: Material + 'static,
+  {
+    self.add_shared(name, Arc::new(material))
+  }
+
+  pub fn add_shared(
+    &mut self,
+    name: &str,
+    material: Arc<dyn Material>,
+  ) -> Result<Arc<dyn Material>, DuplicateMaterial> {
+    if self.materials.contains_key(name) {
+      return Err(DuplicateMaterial {
+        name: name.to_owned(),
+      });
+    }
+    let shared = Arc::new(SharedMaterial::new(material));
+    self.materials.insert(name.to_owned(), shared.clone());
+    Ok(shared)
+  }
+
+  pub fn add_mtl(&mut self, source: &str) -> Result<(), DuplicateMaterial> {
+    for (name, material) in Principled::parse_mtl(source) {
+      self.add(&name, material)?;
+    }
+    Ok(())
+  }
+
+  pub fn add_gltf(&mut self, bytes: &[u8]) -> io::Result<()> {
+    for (name, material) in GltfMaterial::parse(bytes)? {
+      self
+        .add(&name, Principled::from(&material))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    }
+    Ok(())
+  }
+
+  pub fn replace(&self, name: &str, material: Arc<dyn Material>) -> Option<Arc<dyn Material>> {
+    self.materials.get(name).map(|shared| shared.set(material))
+  }
+
+  pub fn get(&self, name: &str) -> Option<Arc<dyn Material>> {
+    self
+      .materials
+      .get(name)
+      .map(|shared| shared.clone() as Arc<dyn Material>)
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    self.materials.contains_key(name)
+  }
+
+  pub fn remove(&mut self, name: &str) -> Option<Arc<dyn Material>> {
+    self.materials.remove(name).map(|shared| shared.get())
+  }
+
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.materials.keys().map(String::as_str)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::material::{DiffuseLight, Lambertian};
+
+  fn emitted(material: &dyn Material) -> Color {
+    let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, material);
+    material.emitted(&ray, &record)
+  }
+
+  #[test]
+  fn duplicate_names_are_rejected() {
+    let mut library = MaterialLibrary::new();
+    let first = library.add("paint", Lambertian::new(Color::one())).unwrap();
+    let error = library.add("paint", Lambertian::new(Color::zero())).err();
+    assert_eq!(
+      error,
+      Some(DuplicateMaterial {
+        name: "paint".to_owned()
+      })
+    );
+    assert!(Arc::ptr_eq(&first, &library.get("paint").unwrap()));
+  }
+
+  #[test]
+  fn replacing_a_material_updates_every_user() {
+    let mut library = MaterialLibrary::new();
+    let handle = library
+      .add("lamp", DiffuseLight::new(Color::one()))
+      .unwrap();
+    assert_eq!(emitted(handle.as_ref()).x, 1.);
+    let previous = library.replace("lamp", Arc::new(DiffuseLight::new(Color::new(2., 2., 2.))));
+    assert!(previous.is_some());
+    assert_eq!(emitted(handle.as_ref()).x, 2.);
+    assert!(
+      library
+        .replace("missing", Arc::new(Lambertian::default()))
+        .is_none()
+    );
+  }
+
+  #[test]
+  fn replacing_while_rendering_reads_a_whole_material() {
+    let mut library = MaterialLibrary::new();
+    let handle = library
+      .add("lamp", DiffuseLight::new(Color::one()))
+      .unwrap();
+    std::thread::scope(|scope| {
+      for _ in 0..4 {
+        scope.spawn(|| {
+          for _ in 0..1000 {
+            let x = emitted(handle.as_ref()).x;
+            assert!(x == x.round() && (1. ..=100.).contains(&x));
+          }
+        });
+      }
+      for i in 2..=100 {
+        let level = i as f64;
+        library.replace("lamp", Arc::new(DiffuseLight::new(Color::new(level, level, level))));
+      }
+    });
+    assert_eq!(emitted(handle.as_ref()).x, 100.);
+  }
+}
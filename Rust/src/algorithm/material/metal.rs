@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 use crate::algorithm::color::Color;
 use crate::algorithm::hittable::HitRecord;
 use crate::algorithm::ray::Ray;
@@ -31,4 +33,56 @@ impl Material for Metal {
       None
     }
   }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    self.pdf(ray_in, hit_record, direction) * self.albedo
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    let direction = direction.normalization();
+    if self.fuzz <= 0. || direction.dot(hit_record.normal) <= 0. {
+      return 0.;
+    }
+    let center = ray_in.direction.reflect(hit_record.normal).normalization();
+    let b = direction.dot(center);
+    let discriminant = b * b - 1. + self.fuzz * self.fuzz;
+    if discriminant <= 0. {
+      return 0.;
+    }
+    let root = discriminant.sqrt();
+    let distance2: f64 = [b - root, b + root]
+      .iter()
+      .filter(|t| **t > 0.)
+      .map(|t| t * t)
+      .sum();
+    distance2 / (4. * PI * self.fuzz * root)
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    self.fuzz <= 0.
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fuzzy_reflection_pdf_is_normalized() {
+    let metal = Metal::new(Color::one(), 0.5);
+    let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &metal);
+    let steps = 200000;
+    let theta_max = 0.5f64.asin();
+    let integral: f64 = (0..steps)
+      .map(|i| {
+        let theta = (i as f64 + 0.5) / steps as f64 * theta_max;
+        let direction = Vec3::new(theta.sin(), 0., theta.cos());
+        2. * PI * theta.sin() * metal.pdf(&ray, &record, direction) * theta_max / steps as f64
+      })
+      .sum();
+    assert!((integral - 1.).abs() < 0.01);
+    assert!(!metal.is_specular(&record));
+    assert!(Metal::new(Color::one(), 0.).is_specular(&record));
+  }
 }
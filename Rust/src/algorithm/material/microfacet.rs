@@ -0,0 +1,294 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::algorithm::complex::Complex;
+use crate::algorithm::random;
+use crate::algorithm::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrowbridgeReitz {
+  pub alpha_x: f64,
+  pub alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+  pub fn new(alpha_x: f64, alpha_y: f64) -> TrowbridgeReitz {
+    TrowbridgeReitz {
+      alpha_x: alpha_x.max(1e-4),
+      alpha_y: alpha_y.max(1e-4),
+    }
+  }
+
+  pub fn from_roughness(roughness_x: f64, roughness_y: f64) -> TrowbridgeReitz {
+    Self::new(roughness_x * roughness_x, roughness_y * roughness_y)
+  }
+
+  pub fn effectively_smooth(&self) -> bool {
+    self.alpha_x.max(self.alpha_y) < 1e-3
+  }
+
+  pub fn d(&self, wm: Vec3) -> f64 {
+    let cos2_theta = wm.z * wm.z;
+    let sin2_theta = (1. - cos2_theta).max(0.);
+    if cos2_theta == 0. {
+      return 0.;
+    }
+    let tan2_theta = sin2_theta / cos2_theta;
+    let (cos2_phi, sin2_phi) = Self::phi2(wm, sin2_theta);
+    let e = tan2_theta
+      * (cos2_phi / (self.alpha_x * self.alpha_x) + sin2_phi / (self.alpha_y * self.alpha_y));
+    1. / (PI * self.alpha_x * self.alpha_y * cos2_theta * cos2_theta * (1. + e) * (1. + e))
+  }
+
+  pub fn lambda(&self, w: Vec3) -> f64 {
+    let cos2_theta = w.z * w.z;
+    let sin2_theta = (1. - cos2_theta).max(0.);
+    if cos2_theta == 0. {
+      return f64::INFINITY;
+    }
+    let tan2_theta = sin2_theta / cos2_theta;
+    let (cos2_phi, sin2_phi) = Self::phi2(w, sin2_theta);
+    let alpha2 = cos2_phi * self.alpha_x * self.alpha_x + sin2_phi * self.alpha_y * self.alpha_y;
+    ((1. + alpha2 * tan2_theta).sqrt() - 1.) / 2.
+  }
+
+  pub fn g1(&self, w: Vec3) -> f64 {
+    1. / (1. + self.lambda(w))
+  }
+
+  pub fn g(&self, wo: Vec3, wi: Vec3) -> f64 {
+    1. / (1. + self.lambda(wo) + self.lambda(wi))
+  }
+
+  pub fn pdf(&self, w: Vec3, wm: Vec3) -> f64 {
+    if w.z == 0. {
+      return 0.;
+    }
+    self.g1(w) / w.z.abs() * self.d(wm) * (w.dot(wm) * w.z.signum()).max(0.)
+  }
+
+  pub fn sample_wm(&self, w: Vec3, u: (f64, f64)) -> Vec3 {
+    let mut wh = Vec3::new(self.alpha_x * w.x, self.alpha_y * w.y, w.z).normalization();
+    if wh.z < 0. {
+      wh = -wh;
+    }
+    let t1 = if wh.z < 0.99999 {
+      Vec3::cross(Vec3::new(0., 0., 1.), wh).normalization()
+    } else {
+      Vec3::new(1., 0., 0.)
+    };
+    let t2 = Vec3::cross(wh, t1);
+
+    let r = u.0.sqrt();
+    let phi = 2. * PI * u.1;
+    let (px, py) = (r * phi.cos(), r * phi.sin());
+    let h = (1. - px * px).sqrt();
+    let s = (1. + wh.z) / 2.;
+    let py = (1. - s) * h + s * py;
+    let pz = (1. - px * px - py * py).max(0.).sqrt();
+    let nh = px * t1 + py * t2 + pz * wh;
+    Vec3::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(1e-6)).normalization()
+  }
+
+  fn phi2(w: Vec3, sin2_theta: f64) -> (f64, f64) {
+    if sin2_theta == 0. {
+      return (1., 0.);
+    }
+    let cos2_phi = (w.x * w.x / sin2_theta).clamp(0., 1.);
+    (cos2_phi, 1. - cos2_phi)
+  }
+}
+
+pub fn reflect(wo: Vec3, n: Vec3) -> Vec3 {
+  -wo + 2. * wo.dot(n) * n
+}
+
+pub fn fresnel_complex(cos_theta_i: f64, eta: Complex) -> f64 {
+  let cos_theta_i = cos_theta_i.clamp(0., 1.);
+  let sin2_theta_i = 1. - cos_theta_i * cos_theta_i;
+  let sin2_theta_t = Complex::from(sin2_theta_i) / (eta * eta);
+  let cos_theta_t = (Complex::from(1.) - sin2_theta_t).sqrt();
+  let cos_i = Complex::from(cos_theta_i);
+  let r_parl = (eta * cos_i - cos_theta_t) / (eta * cos_i + cos_theta_t);
+  let r_perp = (cos_i - eta * cos_theta_t) / (cos_i + eta * cos_theta_t);
+  (r_parl.norm() + r_perp.norm()) / 2.
+}
+
+pub fn refract(wo: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+  let (mut eta, mut n) = (eta, n);
+  let mut cos_theta_i = n.dot(wo);
+  if cos_theta_i < 0. {
+    eta = 1. / eta;
+    cos_theta_i = -cos_theta_i;
+    n = -n;
+  }
+  let sin2_theta_i = (1. - cos_theta_i * cos_theta_i).max(0.);
+  let sin2_theta_t = sin2_theta_i / (eta * eta);
+  if sin2_theta_t >= 1. {
+    return None;
+  }
+  let cos_theta_t = (1. - sin2_theta_t).sqrt();
+  Some(-wo / eta + (cos_theta_i / eta - cos_theta_t) * n)
+}
+
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+  let (mut cos_theta_i, mut eta) = (cos_theta_i.clamp(-1., 1.), eta);
+  if cos_theta_i < 0. {
+    eta = 1. / eta;
+    cos_theta_i = -cos_theta_i;
+  }
+  let sin2_theta_i = 1. - cos_theta_i * cos_theta_i;
+  let sin2_theta_t = sin2_theta_i / (eta * eta);
+  if sin2_theta_t >= 1. {
+    return 1.;
+  }
+  let cos_theta_t = (1. - sin2_theta_t).max(0.).sqrt();
+  let r_parl = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+  let r_perp = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+  (r_parl * r_parl + r_perp * r_perp) / 2.
+}
+
+pub fn sample_dielectric(
+  distribution: &TrowbridgeReitz,
+  eta: f64,
+  wo: Vec3,
+) -> Option<(Vec3, f64)> {
+  let mut rng = random::rng();
+  if eta == 1. {
+    return Some((-wo, 1.));
+  }
+  if distribution.effectively_smooth() {
+    let r = fresnel_dielectric(wo.z, eta);
+    let n = Vec3::new(0., 0., 1.);
+    return if rng.random::<f64>() < r {
+      Some((reflect(wo, n), 1.))
+    } else {
+      refract(wo, n, eta).map(|wi| (wi, 1.))
+    };
+  }
+
+  let wm = distribution.sample_wm(wo, (rng.random::<f64>(), rng.random::<f64>()));
+  let r = fresnel_dielectric(wo.dot(wm), eta);
+  let wi = if rng.random::<f64>() < r {
+    let wi = reflect(wo, wm);
+    if wi.z <= 0. {
+      return None;
+    }
+    wi
+  } else {
+    let wi = refract(wo, wm, eta)?;
+    if wi.z >= 0. {
+      return None;
+    }
+    wi
+  };
+  Some((wi, distribution.g(wo, wi) / distribution.g1(wo)))
+}
+
+fn dielectric_half_vector(eta: f64, wo: Vec3, wi: Vec3) -> Option<Vec3> {
+  let etap = if wi.z > 0. { 1. } else { eta };
+  let wm = etap * wi + wo;
+  if wm.near_zero() {
+    return None;
+  }
+  let wm = wm.normalization();
+  let wm = if wm.z < 0. { -wm } else { wm };
+  (wm.dot(wi) * wi.z >= 0. && wm.dot(wo) * wo.z >= 0.).then_some(wm)
+}
+
+pub fn eval_dielectric(distribution: &TrowbridgeReitz, eta: f64, wo: Vec3, wi: Vec3) -> f64 {
+  if eta == 1. || distribution.effectively_smooth() || wo.z <= 0. || wi.z == 0. {
+    return 0.;
+  }
+  let Some(wm) = dielectric_half_vector(eta, wo, wi) else {
+    return 0.;
+  };
+  let fresnel = fresnel_dielectric(wo.dot(wm), eta);
+  let dg = distribution.d(wm) * distribution.g(wo, wi);
+  if wi.z > 0. {
+    dg * fresnel / (4. * wo.z)
+  } else {
+    let denominator = wi.dot(wm) + wo.dot(wm) / eta;
+    dg * (1. - fresnel) * (wi.dot(wm) * wo.dot(wm)).abs() / (wo.z * denominator * denominator)
+  }
+}
+
+pub fn pdf_dielectric(distribution: &TrowbridgeReitz, eta: f64, wo: Vec3, wi: Vec3) -> f64 {
+  if eta == 1. || distribution.effectively_smooth() || wo.z <= 0. || wi.z == 0. {
+    return 0.;
+  }
+  let Some(wm) = dielectric_half_vector(eta, wo, wi) else {
+    return 0.;
+  };
+  let fresnel = fresnel_dielectric(wo.dot(wm), eta);
+  let pdf = distribution.pdf(wo, wm);
+  if wi.z > 0. {
+    pdf * fresnel / (4. * wo.dot(wm).abs())
+  } else {
+    let denominator = wi.dot(wm) + wo.dot(wm) / eta;
+    pdf * (1. - fresnel) * wi.dot(wm).abs() / (denominator * denominator)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn integrate_hemisphere(f: impl Fn(Vec3) -> f64) -> f64 {
+    let (theta_steps, phi_steps) = (400, 400);
+    let d_theta = 0.5 * PI / theta_steps as f64;
+    let d_phi = 2. * PI / phi_steps as f64;
+    let mut total = 0.;
+    for i in 0..theta_steps {
+      let theta = (i as f64 + 0.5) * d_theta;
+      for j in 0..phi_steps {
+        let phi = (j as f64 + 0.5) * d_phi;
+        let w = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+        total += f(w) * theta.sin();
+      }
+    }
+    total * d_theta * d_phi
+  }
+
+  fn cases() -> Vec<(TrowbridgeReitz, Vec3)> {
+    let oblique = Vec3::new(0.6, 0.2, 0.5).normalization();
+    let mut cases = Vec::new();
+    for distribution in [
+      TrowbridgeReitz::new(0.3, 0.3),
+      TrowbridgeReitz::new(0.5, 0.2),
+    ] {
+      for wo in [Vec3::new(0., 0., 1.), oblique] {
+        cases.push((distribution, wo));
+      }
+    }
+    cases
+  }
+
+  #[test]
+  fn visible_normal_pdf_integrates_to_one() {
+    for (distribution, wo) in cases() {
+      let integral = integrate_hemisphere(|wm| distribution.pdf(wo, wm));
+      assert!((integral - 1.).abs() < 0.01, "{distribution:?} {wo:?}: {integral}");
+    }
+  }
+
+  #[test]
+  fn sampled_normals_follow_the_pdf() {
+    let mut rng = random::rng();
+    let samples = 200000;
+    for (distribution, wo) in cases() {
+      let mut sampled = Vec3::zero();
+      for _ in 0..samples {
+        sampled += distribution.sample_wm(wo, (rng.random::<f64>(), rng.random::<f64>()));
+      }
+      let sampled = sampled / samples as f64;
+      let expected = Vec3::new(
+        integrate_hemisphere(|wm| wm.x * distribution.pdf(wo, wm)),
+        integrate_hemisphere(|wm| wm.y * distribution.pdf(wo, wm)),
+        integrate_hemisphere(|wm| wm.z * distribution.pdf(wo, wm)),
+      );
+      assert!((sampled - expected).len() < 0.01, "{sampled:?} {expected:?}");
+    }
+  }
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::interval::Interval;
+use crate::algorithm::random;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::{self, SolidColor, Texture};
+use crate::algorithm::vec3::Vec3;
+
+use super::{Material, ScatterSample};
+
+pub struct MixMaterial {
+  pub first: Arc<dyn Material>,
+  pub second: Arc<dyn Material>,
+  pub weight: Arc<dyn Texture>,
+}
+
+impl MixMaterial {
+  pub fn new(first: Arc<dyn Material>, second: Arc<dyn Material>, weight: f64) -> MixMaterial {
+    let weight = Arc::new(SolidColor::new(Color::new(weight, weight, weight)));
+    Self::with_texture(first, second, weight)
+  }
+
+  pub fn with_texture(
+    first: Arc<dyn Material>,
+    second: Arc<dyn Material>,
+    weight: Arc<dyn Texture>,
+  ) -> MixMaterial {
+    MixMaterial {
+      first,
+      second,
+      weight,
+    }
+  }
+
+  fn amount(&self, hit_record: &HitRecord) -> f64 {
+    let weight = self
+      .weight
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    Interval::new(0., 1.).clamp(luminance(weight))
+  }
+}
+
+impl Material for MixMaterial {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    if random::rng().random::<f64>() < self.amount(hit_record) {
+      self.second.scatter(ray_in, hit_record)
+    } else {
+      self.first.scatter(ray_in, hit_record)
+    }
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    if random::rng().random::<f64>() < self.amount(hit_record) {
+      self.second.sample(ray_in, hit_record)
+    } else {
+      self.first.sample(ray_in, hit_record)
+    }
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let amount = self.amount(hit_record);
+    (1. - amount) * self.first.eval(ray_in, hit_record, direction)
+      + amount * self.second.eval(ray_in, hit_record, direction)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    let amount = self.amount(hit_record);
+    (1. - amount) * self.first.pdf(ray_in, hit_record, direction)
+      + amount * self.second.pdf(ray_in, hit_record, direction)
+  }
+
+  fn is_specular(&self, hit_record: &HitRecord) -> bool {
+    self.first.is_specular(hit_record) && self.second.is_specular(hit_record)
+  }
+
+  fn opacity(&self, hit_record: &HitRecord) -> f64 {
+    let amount = self.amount(hit_record);
+    (1. - amount) * self.first.opacity(hit_record) + amount * self.second.opacity(hit_record)
+  }
+
+  fn emitted(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    let amount = self.amount(hit_record);
+    (1. - amount) * self.first.emitted(ray_in, hit_record)
+      + amount * self.second.emitted(ray_in, hit_record)
+  }
+
+  fn emission(&self) -> Color {
+    let amount = Interval::new(0., 1.).clamp(luminance(texture::average(self.weight.as_ref())));
+    (1. - amount) * self.first.emission() + amount * self.second.emission()
+  }
+}
@@ -1,15 +1,125 @@
+use std::sync::Arc;
+
 use super::color::Color;
 use super::hittable::HitRecord;
 use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScatterSample {
+  pub ray: Ray,
+  pub attenuation: Color,
+  pub specular: bool,
+}
 
-pub trait Material {
+pub trait Material: Send + Sync {
   fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)>;
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    let (ray, attenuation) = self.scatter(ray_in, hit_record)?;
+    Some(ScatterSample {
+      ray,
+      attenuation,
+      specular: self.is_specular(hit_record),
+    })
+  }
+
+  fn emitted(&self, _ray_in: &Ray, _hit_record: &HitRecord) -> Color {
+    Color::zero()
+  }
+
+  fn emission(&self) -> Color {
+    Color::zero()
+  }
+
+  fn eval(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Color {
+    Color::zero()
+  }
+
+  fn pdf(&self, _ray_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f64 {
+    0.
+  }
+
+  fn is_specular(&self, _hit_record: &HitRecord) -> bool {
+    false
+  }
+
+  fn opacity(&self, _hit_record: &HitRecord) -> f64 {
+    1.
+  }
+}
+
+impl<M> Material for Arc<M>
+where
+  M: Material + ?Sized,
+{
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    (**self).scatter(ray_in, hit_record)
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    (**self).sample(ray_in, hit_record)
+  }
+
+  fn emitted(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    (**self).emitted(ray_in, hit_record)
+  }
+
+  fn emission(&self) -> Color {
+    (**self).emission()
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    (**self).eval(ray_in, hit_record, direction)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    (**self).pdf(ray_in, hit_record, direction)
+  }
+
+  fn is_specular(&self, hit_record: &HitRecord) -> bool {
+    (**self).is_specular(hit_record)
+  }
+
+  fn opacity(&self, hit_record: &HitRecord) -> f64 {
+    (**self).opacity(hit_record)
+  }
 }
 
+mod bump_map;
+mod coated;
+mod conductor;
 mod dielectric;
+mod diffuse_light;
+mod isotropic;
 mod lambertian;
+mod library;
 mod metal;
+mod microfacet;
+mod mix;
+mod normal_map;
+mod oren_nayar;
+mod principled;
+mod rough_dielectric;
+mod subsurface;
+mod thin_film;
+mod volume;
 
-pub use dielectric::Dielectric;
+pub use bump_map::BumpMap;
+pub use coated::Coated;
+pub use conductor::Conductor;
+pub use dielectric::{Dielectric, Dispersion};
+pub use diffuse_light::DiffuseLight;
+pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
+pub use library::{DuplicateMaterial, MaterialLibrary, SharedMaterial};
 pub use metal::Metal;
+pub use microfacet::TrowbridgeReitz;
+pub use mix::MixMaterial;
+pub use normal_map::NormalMap;
+pub use oren_nayar::OrenNayar;
+pub use principled::Principled;
+pub use rough_dielectric::RoughDielectric;
+pub use subsurface::Subsurface;
+pub use thin_film::ThinFilm;
+pub use volume::Volume;
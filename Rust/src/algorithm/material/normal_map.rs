@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::Texture;
+use crate::algorithm::vec3::Vec3;
+
+use super::{Material, ScatterSample};
+
+pub struct NormalMap {
+  pub base: Arc<dyn Material>,
+  pub map: Arc<dyn Texture>,
+  pub strength: f64,
+}
+
+impl NormalMap {
+  pub fn new(base: Arc<dyn Material>, map: Arc<dyn Texture>) -> NormalMap {
+    NormalMap {
+      base,
+      map,
+      strength: 1.,
+    }
+  }
+
+  fn shade<'a>(&self, hit_record: &HitRecord<'a>) -> HitRecord<'a> {
+    let texel = self.map.value(hit_record.u, hit_record.v, hit_record.point);
+    let x = self.strength * (2. * texel.x - 1.);
+    let y = self.strength * (2. * texel.y - 1.);
+    let z = (2. * texel.z - 1.).max(1e-3);
+    let outward = if hit_record.front_face {
+      hit_record.normal
+    } else {
+      -hit_record.normal
+    };
+    let normal = x * hit_record.tangent + y * hit_record.bitangent + z * outward;
+    let mut shaded = *hit_record;
+    shaded.set_shading_normal(if hit_record.front_face {
+      normal
+    } else {
+      -normal
+    });
+    shaded
+  }
+}
+
+impl Material for NormalMap {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    self.base.scatter(ray_in, &self.shade(hit_record))
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    self.base.sample(ray_in, &self.shade(hit_record))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    self.base.eval(ray_in, &self.shade(hit_record), direction)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    self.base.pdf(ray_in, &self.shade(hit_record), direction)
+  }
+
+  fn is_specular(&self, hit_record: &HitRecord) -> bool {
+    self.base.is_specular(hit_record)
+  }
+
+  fn opacity(&self, hit_record: &HitRecord) -> f64 {
+    self.base.opacity(hit_record)
+  }
+
+  fn emitted(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    self.base.emitted(ray_in, hit_record)
+  }
+
+  fn emission(&self) -> Color {
+    self.base.emission()
+  }
+}
@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::onb::Onb;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::{SolidColor, Texture};
+use crate::algorithm::vec3::Vec3;
+
+use super::Material;
+
+pub struct OrenNayar {
+  pub albedo: Arc<dyn Texture>,
+  a: f64,
+  b: f64,
+}
+
+impl OrenNayar {
+  pub fn new(albedo: Color, sigma: f64) -> OrenNayar {
+    Self::with_texture(Arc::new(SolidColor::new(albedo)), sigma)
+  }
+
+  pub fn with_texture(albedo: Arc<dyn Texture>, sigma: f64) -> OrenNayar {
+    let sigma2 = sigma.to_radians().powi(2);
+    OrenNayar {
+      albedo,
+      a: 1. - sigma2 / (2. * (sigma2 + 0.33)),
+      b: 0.45 * sigma2 / (sigma2 + 0.09),
+    }
+  }
+
+  fn reflectance(&self, wo: Vec3, wi: Vec3) -> f64 {
+    let sin_theta_i = (1. - wi.z * wi.z).max(0.).sqrt();
+    let sin_theta_o = (1. - wo.z * wo.z).max(0.).sqrt();
+    let max_cos = if sin_theta_i > 1e-4 && sin_theta_o > 1e-4 {
+      ((wi.x * wo.x + wi.y * wo.y) / (sin_theta_i * sin_theta_o)).max(0.)
+    } else {
+      0.
+    };
+    let (sin_alpha, tan_beta) = if wi.z.abs() > wo.z.abs() {
+      (sin_theta_o, sin_theta_i / wi.z.abs())
+    } else {
+      (sin_theta_i, sin_theta_o / wo.z.abs())
+    };
+    self.a + self.b * max_cos * sin_alpha * tan_beta
+  }
+}
+
+impl Material for OrenNayar {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    let frame = Onb::new(hit_record.normal);
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = Vec3::random_cosine_direction();
+    let albedo = self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    let scattered = Ray::new(hit_record.point, frame.transform(wi));
+    Some((scattered, self.reflectance(wo, wi) * albedo))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let frame = Onb::new(hit_record.normal);
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    if wi.z <= 0. {
+      return Color::zero();
+    }
+    let albedo = self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    (self.reflectance(wo, wi) * wi.z / PI) * albedo
+  }
+
+  fn pdf(&self, _: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    hit_record.normal.dot(direction.normalization()).max(0.) / PI
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::material::Lambertian;
+
+  fn incoming(cos_theta: f64) -> Ray {
+    let direction = Vec3::new((1. - cos_theta * cos_theta).sqrt(), 0., -cos_theta);
+    Ray::new(-direction, direction)
+  }
+
+  #[test]
+  fn smooth_surface_is_lambertian() {
+    let albedo = Color::new(0.2, 0.5, 0.8);
+    let smooth = OrenNayar::new(albedo, 0.);
+    let lambertian = Lambertian::new(albedo);
+    let ray = incoming(0.6);
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &smooth);
+    for _ in 0..100 {
+      let direction = Vec3::random_unit_vector();
+      let expected = lambertian.eval(&ray, &record, direction);
+      assert!((smooth.eval(&ray, &record, direction) - expected).len() < 1e-12);
+      assert_eq!(smooth.pdf(&ray, &record, direction), lambertian.pdf(&ray, &record, direction));
+    }
+  }
+
+  #[test]
+  fn reflected_energy_stays_below_the_albedo() {
+    let albedo = Color::new(0.9, 0.9, 0.9);
+    let (theta_steps, phi_steps) = (200, 400);
+    let d_theta = 0.5 * PI / theta_steps as f64;
+    let d_phi = 2. * PI / phi_steps as f64;
+    for sigma in [20., 45., 90.] {
+      let material = OrenNayar::new(albedo, sigma);
+      for cos_theta in [1., 0.7, 0.3, 0.05] {
+        let ray = incoming(cos_theta);
+        let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &material);
+        let mut reflected = 0.;
+        for i in 0..theta_steps {
+          let theta = (i as f64 + 0.5) * d_theta;
+          for j in 0..phi_steps {
+            let phi = (j as f64 + 0.5) * d_phi;
+            let wi = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+            reflected += material.eval(&ray, &record, wi).x * theta.sin() * d_theta * d_phi;
+          }
+        }
+        assert!(reflected <= albedo.x, "{sigma} {cos_theta}: {reflected}");
+      }
+    }
+  }
+}
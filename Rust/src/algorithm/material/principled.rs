@@ -0,0 +1,488 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::algorithm::color::{Color, luminance};
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::random;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::microfacet::{
+  TrowbridgeReitz, eval_dielectric, pdf_dielectric, reflect, sample_dielectric,
+};
+use super::{Material, ScatterSample};
+
+const DIFFUSE: usize = 0;
+const SPECULAR: usize = 1;
+const CLEARCOAT: usize = 2;
+const TRANSMISSION: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct Principled {
+  pub base_color: Color,
+  pub metallic: f64,
+  pub roughness: f64,
+  pub specular: f64,
+  pub specular_tint: f64,
+  pub sheen: f64,
+  pub sheen_tint: f64,
+  pub clearcoat: f64,
+  pub clearcoat_gloss: f64,
+  pub transmission: f64,
+  pub ior: f64,
+  pub emission: Color,
+  pub opacity: f64,
+}
+
+impl Default for Principled {
+  fn default() -> Self {
+    Principled {
+      base_color: Color::new(0.8, 0.8, 0.8),
+      metallic: 0.,
+      roughness: 0.5,
+      specular: 0.5,
+      specular_tint: 0.,
+      sheen: 0.,
+      sheen_tint: 0.5,
+      clearcoat: 0.,
+      clearcoat_gloss: 1.,
+      transmission: 0.,
+      ior: 1.5,
+      emission: Color::zero(),
+      opacity: 1.,
+    }
+  }
+}
+
+impl Principled {
+  pub fn new(base_color: Color) -> Principled {
+    Principled {
+      base_color,
+      ..Default::default()
+    }
+  }
+
+  pub fn parse_mtl(source: &str) -> Vec<(String, Principled)> {
+    let mut materials = Vec::new();
+    let mut current: Option<MtlEntry> = None;
+    for line in source.lines() {
+      let line = line.split('#').next().unwrap_or_default();
+      let mut tokens = line.split_whitespace();
+      let Some(keyword) = tokens.next() else {
+        continue;
+      };
+      if keyword == "newmtl" {
+        materials.extend(current.take().map(MtlEntry::finish));
+        let name = tokens.collect::<Vec<_>>().join(" ");
+        current = Some(MtlEntry::new(name));
+        continue;
+      }
+      let Some(entry) = current.as_mut() else {
+        continue;
+      };
+      let values: Vec<f64> = tokens.filter_map(|token| token.parse().ok()).collect();
+      entry.apply(keyword, &values);
+    }
+    materials.extend(current.map(MtlEntry::finish));
+    materials
+  }
+
+  fn tint(&self) -> Color {
+    let lum = luminance(self.base_color);
+    if lum > 0. {
+      self.base_color / lum
+    } else {
+      Color::one()
+    }
+  }
+
+  fn dielectric_specular_color(&self) -> Color {
+    self.specular * 0.08 * lerp_color(Color::one(), self.tint(), self.specular_tint)
+  }
+
+  fn specular_color(&self) -> Color {
+    lerp_color(self.dielectric_specular_color(), self.base_color, self.metallic)
+  }
+
+  fn diffuse_transmission(&self, cos_theta: f64) -> f64 {
+    let fresnel =
+      lerp_color(self.dielectric_specular_color(), Color::one(), schlick_weight(cos_theta));
+    (1. - luminance(fresnel)).max(0.)
+  }
+
+  fn distribution(&self) -> TrowbridgeReitz {
+    TrowbridgeReitz::from_roughness(self.roughness, self.roughness)
+  }
+
+  fn clearcoat_distribution(&self) -> TrowbridgeReitz {
+    let alpha = lerp(0.1, 0.001, self.clearcoat_gloss);
+    TrowbridgeReitz::new(alpha, alpha)
+  }
+
+  fn diffuse_weight(&self) -> f64 {
+    (1. - self.metallic) * (1. - self.transmission)
+  }
+
+  fn specular_weight(&self) -> f64 {
+    1. - (1. - self.metallic) * self.transmission
+  }
+
+  fn transmission_weight(&self) -> f64 {
+    (1. - self.metallic) * self.transmission
+  }
+
+  fn lobe_probabilities(&self, wo: Vec3) -> [f64; 4] {
+    let fresnel = schlick_weight(wo.z);
+    let mut probabilities = [
+      self.diffuse_weight()
+        * self.diffuse_transmission(wo.z)
+        * luminance(self.base_color).max(0.05),
+      self.specular_weight() * luminance(lerp_color(self.specular_color(), Color::one(), fresnel)),
+      0.25 * self.clearcoat * lerp(0.04, 1., fresnel),
+      self.transmission_weight(),
+    ];
+    let total: f64 = probabilities.iter().sum();
+    if total <= 0. {
+      return [1., 0., 0., 0.];
+    }
+    probabilities.iter_mut().for_each(|p| *p /= total);
+    probabilities
+  }
+
+  fn eval_reflection(&self, wo: Vec3, wi: Vec3) -> Color {
+    if wo.z <= 0. || wi.z <= 0. {
+      return Color::zero();
+    }
+    let wm = (wo + wi).normalization();
+    let cos_theta_d = wi.dot(wm);
+    let fresnel = schlick_weight(cos_theta_d);
+
+    let fd90 = 0.5 + 2. * self.roughness * cos_theta_d * cos_theta_d;
+    let fd = lerp(1., fd90, schlick_weight(wi.z)) * lerp(1., fd90, schlick_weight(wo.z));
+    let diffuse = (fd / PI) * self.base_color;
+    let sheen = self.sheen * fresnel * lerp_color(Color::one(), self.tint(), self.sheen_tint);
+
+    let distribution = self.distribution();
+    let specular = if distribution.effectively_smooth() {
+      Color::zero()
+    } else {
+      distribution.d(wm) * distribution.g(wo, wi) / (4. * wo.z * wi.z)
+        * lerp_color(self.specular_color(), Color::one(), fresnel)
+    };
+
+    let clearcoat_distribution = self.clearcoat_distribution();
+    let clearcoat = 0.25
+      * self.clearcoat
+      * clearcoat_distribution.d(wm)
+      * clearcoat_distribution.g(wo, wi)
+      * lerp(0.04, 1., fresnel)
+      / (4. * wo.z * wi.z);
+
+    let diffuse_weight =
+      self.diffuse_weight() * self.diffuse_transmission(wo.z) * self.diffuse_transmission(wi.z);
+    let f = diffuse_weight * (diffuse + sheen)
+      + self.specular_weight() * specular
+      + Color::new(clearcoat, clearcoat, clearcoat);
+    wi.z * f
+  }
+
+  fn pdf_reflection(&self, wo: Vec3, wi: Vec3, probabilities: &[f64; 4]) -> f64 {
+    if wo.z <= 0. || wi.z <= 0. {
+      return 0.;
+    }
+    let wm = (wo + wi).normalization();
+    let jacobian = 4. * wo.dot(wm);
+    let distribution = self.distribution();
+    let specular = if distribution.effectively_smooth() {
+      0.
+    } else {
+      distribution.pdf(wo, wm) / jacobian
+    };
+    probabilities[DIFFUSE] * wi.z / PI
+      + probabilities[SPECULAR] * specular
+      + probabilities[CLEARCOAT] * self.clearcoat_distribution().pdf(wo, wm) / jacobian
+  }
+
+  fn eta(&self, hit_record: &HitRecord) -> f64 {
+    if hit_record.front_face {
+      self.ior
+    } else {
+      1. / self.ior
+    }
+  }
+
+  fn transmission_tint(&self, wi: Vec3) -> Color {
+    if wi.z < 0. {
+      self.base_color
+    } else {
+      Color::one()
+    }
+  }
+
+  fn eval_local(&self, wo: Vec3, wi: Vec3, eta: f64) -> Color {
+    let transmission = eval_dielectric(&self.distribution(), eta, wo, wi);
+    self.eval_reflection(wo, wi)
+      + self.transmission_weight() * transmission * self.transmission_tint(wi)
+  }
+
+  fn pdf_local(&self, wo: Vec3, wi: Vec3, eta: f64, probabilities: &[f64; 4]) -> f64 {
+    self.pdf_reflection(wo, wi, probabilities)
+      + probabilities[TRANSMISSION] * pdf_dielectric(&self.distribution(), eta, wo, wi)
+  }
+}
+
+impl Material for Principled {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    self
+      .sample(ray_in, hit_record)
+      .map(|sample| (sample.ray, sample.attenuation))
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    if wo.z <= 0. {
+      return None;
+    }
+
+    let mut rng = random::rng();
+    let probabilities = self.lobe_probabilities(wo);
+    let mut u = rng.random::<f64>();
+    let mut lobe = DIFFUSE;
+    while lobe < TRANSMISSION && u >= probabilities[lobe] {
+      u -= probabilities[lobe];
+      lobe += 1;
+    }
+
+    let eta = self.eta(hit_record);
+    let distribution = self.distribution();
+    let smooth = distribution.effectively_smooth();
+    let delta = |wi: Vec3, attenuation: Color| {
+      Some(ScatterSample {
+        ray: Ray::new(hit_record.point, frame.transform(wi)),
+        attenuation,
+        specular: true,
+      })
+    };
+    let u2 = (rng.random::<f64>(), rng.random::<f64>());
+    let wi = match lobe {
+      DIFFUSE => Vec3::random_cosine_direction(),
+      SPECULAR if smooth => {
+        let fresnel = lerp_color(self.specular_color(), Color::one(), schlick_weight(wo.z));
+        let attenuation = self.specular_weight() / probabilities[SPECULAR] * fresnel;
+        return delta(reflect(wo, Vec3::new(0., 0., 1.)), attenuation);
+      }
+      SPECULAR => reflect(wo, distribution.sample_wm(wo, u2)),
+      CLEARCOAT => reflect(wo, self.clearcoat_distribution().sample_wm(wo, u2)),
+      _ if probabilities[TRANSMISSION] <= 0. => return None,
+      _ => {
+        let (wi, weight) = sample_dielectric(&distribution, eta, wo)?;
+        if smooth || eta == 1. {
+          let attenuation = weight * self.transmission_weight() / probabilities[TRANSMISSION]
+            * self.transmission_tint(wi);
+          return delta(wi, attenuation);
+        }
+        wi
+      }
+    };
+
+    let pdf = self.pdf_local(wo, wi, eta, &probabilities);
+    if pdf <= 0. {
+      return None;
+    }
+    Some(ScatterSample {
+      ray: Ray::new(hit_record.point, frame.transform(wi)),
+      attenuation: self.eval_local(wo, wi, eta) / pdf,
+      specular: false,
+    })
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    self.eval_local(wo, wi, self.eta(hit_record))
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    self.pdf_local(wo, wi, self.eta(hit_record), &self.lobe_probabilities(wo))
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    self.distribution().effectively_smooth() && self.diffuse_weight() <= 0. && self.clearcoat <= 0.
+  }
+
+  fn opacity(&self, _: &HitRecord) -> f64 {
+    self.opacity
+  }
+
+  fn emitted(&self, _ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    if hit_record.front_face {
+      self.emission
+    } else {
+      Color::zero()
+    }
+  }
+
+  fn emission(&self) -> Color {
+    self.emission
+  }
+}
+
+struct MtlEntry {
+  name: String,
+  material: Principled,
+  shininess: Option<f64>,
+  roughness: Option<f64>,
+}
+
+impl MtlEntry {
+  fn new(name: String) -> MtlEntry {
+    MtlEntry {
+      name,
+      material: Principled::default(),
+      shininess: None,
+      roughness: None,
+    }
+  }
+
+  fn apply(&mut self, keyword: &str, values: &[f64]) {
+    let color = match values {
+      [r, g, b, ..] => Color::new(*r, *g, *b),
+      [v, ..] => Color::new(*v, *v, *v),
+      [] => return,
+    };
+    let value = values[0];
+    let material = &mut self.material;
+    match keyword {
+      "Kd" => material.base_color = color,
+      "Ks" => material.specular = luminance(color).clamp(0., 1.),
+      "Ke" => material.emission = color,
+      "Ns" => self.shininess = Some(value),
+      "Ni" => material.ior = value,
+      "d" => material.opacity = value.clamp(0., 1.),
+      "Tr" => material.opacity = (1. - value).clamp(0., 1.),
+      "Pr" => self.roughness = Some(value),
+      "Pm" => material.metallic = value,
+      "Ps" => material.sheen = value,
+      "Pc" => material.clearcoat = value,
+      "Pcr" => material.clearcoat_gloss = 1. - value,
+      _ => {}
+    }
+  }
+
+  fn finish(self) -> (String, Principled) {
+    let mut material = self.material;
+    material.roughness = match (self.roughness, self.shininess) {
+      (Some(roughness), _) => roughness,
+      (None, Some(shininess)) => (2. / (shininess.max(0.) + 2.)).sqrt().sqrt(),
+      (None, None) => material.roughness,
+    };
+    (self.name, material)
+  }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+  (1. - t) * a + t * b
+}
+
+fn lerp_color(a: Color, b: Color, t: f64) -> Color {
+  (1. - t) * a + t * b
+}
+
+fn schlick_weight(cos_theta: f64) -> f64 {
+  (1. - cos_theta).clamp(0., 1.).powi(5)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mtl_dissolve_maps_to_opacity() {
+    let materials =
+      Principled::parse_mtl("newmtl leaf\nKd 0.2 0.6 0.1\nd 0.5\nnewmtl fence\nTr 0.75\nNi 1.3\n");
+    let (name, leaf) = &materials[0];
+    assert_eq!(name, "leaf");
+    assert_eq!(leaf.opacity, 0.5);
+    assert_eq!(leaf.transmission, 0.);
+    let (_, fence) = &materials[1];
+    assert_eq!(fence.opacity, 0.25);
+    assert_eq!(fence.transmission, 0.);
+    assert_eq!(fence.ior, 1.3);
+  }
+
+  #[test]
+  fn rough_transmission_keeps_next_event_estimation() {
+    let material = Principled {
+      base_color: Color::new(0.8, 0.8, 0.8),
+      roughness: 0.7,
+      transmission: 0.5,
+      ..Principled::default()
+    };
+    let ray = Ray::new(Vec3::new(-0.5, 0., 1.), Vec3::new(0.5, 0., -1.));
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &material);
+    assert!(!material.is_specular(&record));
+
+    let samples = 400000;
+    let sampled = (0..samples)
+      .filter_map(|_| material.sample(&ray, &record))
+      .inspect(|sample| assert!(!sample.specular))
+      .map(|sample| luminance(sample.attenuation))
+      .sum::<f64>()
+      / samples as f64;
+    let evaluated = (0..samples)
+      .map(|_| 4. * PI * luminance(material.eval(&ray, &record, Vec3::random_unit_vector())))
+      .sum::<f64>()
+      / samples as f64;
+    assert!((sampled - evaluated).abs() < 0.03 * sampled);
+  }
+
+  #[test]
+  fn dielectric_lobes_stay_energy_bounded() {
+    let material = Principled {
+      base_color: Color::one(),
+      roughness: 0.3,
+      specular: 1.,
+      ..Principled::default()
+    };
+    for direction in [Vec3::new(0., 0., -1.), Vec3::new(1., 0., -1.)] {
+      let ray = Ray::new(-direction, direction);
+      let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &material);
+      let samples = 200000;
+      let albedo = (0..samples)
+        .filter_map(|_| material.sample(&ray, &record))
+        .map(|sample| luminance(sample.attenuation))
+        .sum::<f64>()
+        / samples as f64;
+      assert!(albedo <= 1., "{direction:?}: {albedo}");
+    }
+  }
+
+  #[test]
+  fn smooth_lobes_are_sampled_as_delta_events() {
+    let mirror = Principled {
+      metallic: 1.,
+      roughness: 0.,
+      ..Principled::default()
+    };
+    let ray = Ray::new(Vec3::new(0., 0., 1.), Vec3::new(0., 0., -1.));
+    let record = HitRecord::new(Vec3::zero(), 1., Vec3::new(0., 0., 1.), ray, &mirror);
+    assert!(mirror.is_specular(&record));
+    let glass = Principled {
+      roughness: 0.,
+      transmission: 0.5,
+      ..Principled::default()
+    };
+    assert!(!glass.is_specular(&record));
+    assert!(
+      (0..1000)
+        .filter_map(|_| glass.sample(&ray, &record))
+        .any(|s| s.specular)
+    );
+  }
+}
@@ -0,0 +1,177 @@
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::Material;
+use super::microfacet::{TrowbridgeReitz, eval_dielectric, pdf_dielectric, sample_dielectric};
+
+pub struct RoughDielectric {
+  pub refraction_index: f64,
+  pub distribution: TrowbridgeReitz,
+}
+
+impl RoughDielectric {
+  pub fn new(refraction_index: f64, roughness: f64) -> RoughDielectric {
+    Self::anisotropic(refraction_index, roughness, roughness)
+  }
+
+  pub fn anisotropic(refraction_index: f64, roughness_u: f64, roughness_v: f64) -> RoughDielectric {
+    RoughDielectric {
+      refraction_index,
+      distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v),
+    }
+  }
+
+  fn eta(&self, hit_record: &HitRecord) -> f64 {
+    if hit_record.front_face {
+      self.refraction_index
+    } else {
+      1. / self.refraction_index
+    }
+  }
+}
+
+impl Material for RoughDielectric {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    let eta = self.eta(hit_record);
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let (wi, weight) = sample_dielectric(&self.distribution, eta, wo)?;
+    let scattered = Ray::new(hit_record.point, frame.transform(wi));
+    Some((scattered, Color::new(weight, weight, weight)))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    let f = eval_dielectric(&self.distribution, self.eta(hit_record), wo, wi);
+    Color::new(f, f, f)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    pdf_dielectric(&self.distribution, self.eta(hit_record), wo, wi)
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    self.distribution.effectively_smooth() || self.refraction_index == 1.
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::f64::consts::PI;
+
+  use super::*;
+
+  fn integrate_sphere(f: impl Fn(Vec3) -> f64) -> f64 {
+    let (theta_steps, phi_steps) = (800, 400);
+    let d_theta = PI / theta_steps as f64;
+    let d_phi = 2. * PI / phi_steps as f64;
+    let mut total = 0.;
+    for i in 0..theta_steps {
+      let theta = (i as f64 + 0.5) * d_theta;
+      for j in 0..phi_steps {
+        let phi = (j as f64 + 0.5) * d_phi;
+        let w = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+        total += f(w) * theta.sin();
+      }
+    }
+    total * d_theta * d_phi
+  }
+
+  fn record<'a>(ray: Ray, outward: Vec3, material: &'a dyn Material) -> HitRecord<'a> {
+    HitRecord::new(Vec3::zero(), 1., outward, ray, material)
+  }
+
+  #[test]
+  fn reflection_and_transmission_pdf_integrates_to_one() {
+    let glass = RoughDielectric::new(1.5, 0.5);
+    let direction = Vec3::new(0.4, 0.1, -1.);
+    let up = Vec3::new(0., 0., 1.);
+    for (ray, outward) in [
+      (Ray::new(-direction, direction), up),
+      (Ray::new(direction, -direction), up),
+    ] {
+      let record = record(ray, outward, &glass);
+      let samples = 100000;
+      let mut scattered = 0;
+      for _ in 0..samples {
+        let Some((sample, attenuation)) = glass.scatter(&ray, &record) else {
+          continue;
+        };
+        scattered += 1;
+        let pdf = glass.pdf(&ray, &record, sample.direction);
+        let expected = glass.eval(&ray, &record, sample.direction) / pdf;
+        assert!((attenuation - expected).len() < 1e-9 * attenuation.len().max(1.));
+      }
+      let integral = integrate_sphere(|wi| glass.pdf(&ray, &record, wi));
+      assert!(integral <= 1.01, "{integral}");
+      assert!(
+        (integral - scattered as f64 / samples as f64).abs() < 0.01,
+        "{integral} {scattered}"
+      );
+    }
+  }
+
+  #[test]
+  fn transmission_is_reciprocal_up_to_the_squared_index() {
+    let glass = RoughDielectric::new(1.5, 0.4);
+    let up = Vec3::new(0., 0., 1.);
+    let outside = Vec3::new(0.3, -0.2, 1.).normalization();
+    let inside = Vec3::new(-0.1, 0.25, -1.).normalization();
+    let from_outside = Ray::new(outside, -outside);
+    let from_inside = Ray::new(inside, -inside);
+    let forward = glass
+      .eval(&from_outside, &record(from_outside, up, &glass), inside)
+      .x
+      / inside.z.abs();
+    let backward = glass
+      .eval(&from_inside, &record(from_inside, up, &glass), outside)
+      .x
+      / outside.z.abs();
+    assert!(forward > 0.);
+    assert!((forward - 1.5 * 1.5 * backward).abs() < 1e-9 * forward);
+
+    let reflected = Vec3::new(-0.5, 0.1, 1.).normalization();
+    let ray = Ray::new(reflected, -reflected);
+    let there = glass
+      .eval(&from_outside, &record(from_outside, up, &glass), reflected)
+      .x;
+    let back = glass.eval(&ray, &record(ray, up, &glass), outside).x;
+    assert!((there / reflected.z - back / outside.z).abs() < 1e-9 * there);
+  }
+
+  #[test]
+  fn rough_glass_slab_conserves_energy() {
+    let glass = RoughDielectric::new(1.5, 0.3);
+    let samples = 50000;
+    let mut throughput = 0.;
+    for _ in 0..samples {
+      let mut ray = Ray::new(Vec3::zero(), Vec3::new(0.2, 0., -1.));
+      let mut weight = 1.;
+      for _ in 0..100 {
+        let outward = Vec3::new(0., 0., if ray.origin.z < -0.5 { -1. } else { 1. });
+        let Some((scattered, attenuation)) = glass.scatter(&ray, &record(ray, outward, &glass))
+        else {
+          weight = 0.;
+          break;
+        };
+        weight *= attenuation.x;
+        let inside = scattered.direction.z * outward.z < 0.;
+        if !inside {
+          break;
+        }
+        let z = if outward.z > 0. { -1. } else { 0. };
+        ray = Ray::new(Vec3::new(0., 0., z), scattered.direction);
+      }
+      throughput += weight;
+    }
+    let throughput = throughput / samples as f64;
+    assert!(throughput <= 1. && throughput > 0.9, "{throughput}");
+  }
+}
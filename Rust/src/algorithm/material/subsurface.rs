@@ -0,0 +1,205 @@
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::medium::{HenyeyGreenstein, sample_free_flight};
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+use super::microfacet::{TrowbridgeReitz, eval_dielectric, pdf_dielectric, sample_dielectric};
+use super::{Material, ScatterSample};
+
+pub struct Subsurface {
+  pub refraction_index: f64,
+  pub sigma_a: Color,
+  pub sigma_s: Color,
+  pub g: f64,
+  pub distribution: TrowbridgeReitz,
+}
+
+impl Subsurface {
+  pub fn new(refraction_index: f64, sigma_a: Color, sigma_s: Color, g: f64) -> Subsurface {
+    Self::rough(refraction_index, sigma_a, sigma_s, g, 0.)
+  }
+
+  pub fn rough(
+    refraction_index: f64,
+    sigma_a: Color,
+    sigma_s: Color,
+    g: f64,
+    roughness: f64,
+  ) -> Subsurface {
+    Subsurface {
+      refraction_index,
+      sigma_a,
+      sigma_s,
+      g,
+      distribution: TrowbridgeReitz::from_roughness(roughness, roughness),
+    }
+  }
+
+  pub fn from_albedo(
+    refraction_index: f64,
+    albedo: Color,
+    mean_free_path: Color,
+    g: f64,
+  ) -> Subsurface {
+    let sigma_t = Color::new(1. / mean_free_path.x, 1. / mean_free_path.y, 1. / mean_free_path.z);
+    let sigma_s = albedo * sigma_t;
+    Self::new(refraction_index, sigma_t - sigma_s, sigma_s, g)
+  }
+
+  fn eta(&self, hit_record: &HitRecord) -> f64 {
+    if hit_record.front_face {
+      self.refraction_index
+    } else {
+      1. / self.refraction_index
+    }
+  }
+
+  fn boundary_transmittance(&self, ray_in: &Ray, hit_record: &HitRecord) -> Color {
+    if hit_record.front_face {
+      return Color::one();
+    }
+    let distance = hit_record.t * ray_in.direction.len();
+    (-distance * (self.sigma_a + self.sigma_s)).exp()
+  }
+
+  fn cross_boundary(
+    &self,
+    ray_in: &Ray,
+    hit_record: &HitRecord,
+    weight: Color,
+  ) -> Option<ScatterSample> {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let (wi, masking) = sample_dielectric(&self.distribution, self.eta(hit_record), wo)?;
+    Some(ScatterSample {
+      ray: Ray::new(hit_record.point, frame.transform(wi)),
+      attenuation: masking * weight,
+      specular: self.is_specular(hit_record),
+    })
+  }
+}
+
+impl Material for Subsurface {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    self
+      .sample(ray_in, hit_record)
+      .map(|sample| (sample.ray, sample.attenuation))
+  }
+
+  fn sample(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterSample> {
+    if hit_record.front_face {
+      return self.cross_boundary(ray_in, hit_record, Color::one());
+    }
+
+    let ray_length = ray_in.direction.len();
+    let sigma_t = self.sigma_a + self.sigma_s;
+    let flight = sample_free_flight(sigma_t, self.sigma_s, hit_record.t * ray_length);
+    if flight.scattered {
+      let point = ray_in.at(flight.distance / ray_length);
+      let direction = HenyeyGreenstein::sample_direction(ray_in.direction, self.g);
+      return Some(ScatterSample {
+        ray: Ray::new(point, direction),
+        attenuation: flight.weight,
+        specular: true,
+      });
+    }
+    self.cross_boundary(ray_in, hit_record, flight.weight)
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    let f = eval_dielectric(&self.distribution, self.eta(hit_record), wo, wi);
+    f * self.boundary_transmittance(ray_in, hit_record)
+  }
+
+  fn pdf(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+    let frame = hit_record.frame();
+    let wo = frame.to_local(-ray_in.direction.normalization());
+    let wi = frame.to_local(direction.normalization());
+    let reached = self
+      .boundary_transmittance(ray_in, hit_record)
+      .dot(Vec3::one())
+      / 3.;
+    reached * pdf_dielectric(&self.distribution, self.eta(hit_record), wo, wi)
+  }
+
+  fn is_specular(&self, _: &HitRecord) -> bool {
+    self.distribution.effectively_smooth() || self.refraction_index == 1.
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use super::*;
+  use crate::algorithm::camera::Camera;
+  use crate::algorithm::hittable_list::HittableList;
+  use crate::algorithm::light::{LightList, PointLight};
+  use crate::algorithm::quad::Quad;
+
+  fn slab(material: Subsurface) -> HittableList {
+    let material = Arc::new(material);
+    let mut world = HittableList::default();
+    world.add(Quad::new(
+      Vec3::new(-5., -5., -1.),
+      Vec3::new(10., 0., 0.),
+      Vec3::new(0., 10., 0.),
+      material.clone(),
+    ));
+    world.add(Quad::new(
+      Vec3::new(-5., -5., -2.),
+      Vec3::new(0., 10., 0.),
+      Vec3::new(10., 0., 0.),
+      material,
+    ));
+    world
+  }
+
+  fn render(material: Subsurface) -> Vec<u8> {
+    let mut lights = LightList::default();
+    lights.add(PointLight::new(Vec3::new(0., 0., -3.), Color::new(20., 20., 20.)));
+    let mut camera =
+      Camera::new(8, 8, 16, 16, Vec3::zero(), Vec3::new(0., 0., -1.), Vec3::new(0., 1., 0.));
+    camera.vfov = 30.;
+    camera.background = Arc::new(Color::zero());
+    camera.seed = Some(5);
+    camera.render(&slab(material), &lights)
+  }
+
+  #[test]
+  fn point_light_shines_through_a_rough_slab() {
+    let (sigma_a, sigma_s) = (Color::new(0.05, 0.05, 0.05), Color::new(1., 1., 1.));
+    let rough = Subsurface::rough(1.3, sigma_a, sigma_s, 0., 0.3);
+    let image = render(rough);
+    let lit = image.chunks(4).filter(|pixel| pixel[0] > 0).count();
+    assert!(lit > 48, "{lit}");
+
+    let smooth = Subsurface::new(1.3, sigma_a, sigma_s, 0.);
+    assert!(render(smooth).chunks(4).all(|pixel| pixel[0] == 0));
+  }
+
+  #[test]
+  fn boundary_samples_match_eval_over_pdf() {
+    let material = Subsurface::rough(1.3, Color::new(0.2, 0.4, 0.6), Color::one(), 0.3, 0.4);
+    let ray = Ray::new(Vec3::new(0.1, 0., 0.), Vec3::new(0.3, 0.1, 1.));
+    let record = HitRecord::new(Vec3::new(0.4, 0.1, 1.), 1., Vec3::new(0., 0., 1.), ray, &material);
+    assert!(!record.front_face);
+    assert!(!material.is_specular(&record));
+    for _ in 0..10000 {
+      let Some(sample) = material.sample(&ray, &record) else {
+        continue;
+      };
+      if sample.specular {
+        continue;
+      }
+      let direction = sample.ray.direction;
+      let expected =
+        material.eval(&ray, &record, direction) / material.pdf(&ray, &record, direction);
+      assert!((sample.attenuation - expected).len() < 1e-9 * expected.len().max(1.));
+    }
+  }
+}
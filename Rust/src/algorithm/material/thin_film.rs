@@ -0,0 +1,85 @@
+use std::f64::consts::PI;
+
+use crate::algorithm::complex::Complex;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::spectrum::Wavelengths;
+
+const RGB_WAVELENGTHS: [f64; 3] = [630., 532., 465.];
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThinFilm {
+  pub thickness: f64,
+  pub refraction_index: f64,
+}
+
+impl ThinFilm {
+  pub fn new(thickness: f64, refraction_index: f64) -> ThinFilm {
+    ThinFilm {
+      thickness,
+      refraction_index,
+    }
+  }
+
+  pub fn reflectance(&self, cos_theta_i: f64, outer: f64, substrate: Complex, lambda: f64) -> f64 {
+    let cos_theta_i = cos_theta_i.clamp(0., 1.);
+    let sin2_theta_i = Complex::from(1. - cos_theta_i * cos_theta_i);
+    let n1 = Complex::from(outer);
+    let n2 = Complex::from(self.refraction_index);
+    let n3 = substrate;
+    let one = Complex::from(1.);
+    let cos1 = Complex::from(cos_theta_i);
+    let cos2 = (one - (n1 * n1) / (n2 * n2) * sin2_theta_i).sqrt();
+    let cos3 = (one - (n1 * n1) / (n3 * n3) * sin2_theta_i).sqrt();
+
+    let delta = Complex::from(4. * PI * self.thickness / lambda) * n2 * cos2;
+    let phase = (Complex::new(0., 1.) * delta).exp();
+    let airy = |r12: Complex, r23: Complex| {
+      let r = (r12 + r23 * phase) / (one + r12 * r23 * phase);
+      r.norm()
+    };
+
+    let s12 = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+    let s23 = (n2 * cos2 - n3 * cos3) / (n2 * cos2 + n3 * cos3);
+    let p12 = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+    let p23 = (n3 * cos2 - n2 * cos3) / (n3 * cos2 + n2 * cos3);
+    ((airy(s12, s23) + airy(p12, p23)) / 2.).clamp(0., 1.)
+  }
+}
+
+pub fn film_wavelengths(ray_in: &Ray) -> ([f64; 3], Option<Wavelengths>) {
+  match ray_in.wavelengths {
+    Some(wavelengths) => ([wavelengths.hero(); 3], Some(wavelengths.terminate_secondary())),
+    None => (RGB_WAVELENGTHS, None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::algorithm::material::microfacet::{fresnel_complex, fresnel_dielectric};
+
+  #[test]
+  fn zero_thickness_matches_bare_fresnel() {
+    let film = ThinFilm::new(0., 1.33);
+    let gold = Complex::new(0.143, 3.983);
+    for cos_theta in [1., 0.8, 0.5, 0.2, 0.05] {
+      for lambda in [450., 550., 650.] {
+        let glass = film.reflectance(cos_theta, 1., Complex::from(1.5), lambda);
+        assert!((glass - fresnel_dielectric(cos_theta, 1.5)).abs() < 1e-9);
+        let metal = film.reflectance(cos_theta, 1., gold, lambda);
+        assert!((metal - fresnel_complex(cos_theta, gold)).abs() < 1e-9);
+      }
+    }
+  }
+
+  #[test]
+  fn quarter_wave_coating_cancels_reflection() {
+    let (substrate, lambda) = (1.5f64, 550.);
+    let index = substrate.sqrt();
+    let coating = ThinFilm::new(lambda / (4. * index), index);
+    let reflectance = |lambda: f64| coating.reflectance(1., 1., Complex::from(substrate), lambda);
+    assert!(reflectance(lambda) < 1e-9);
+    assert!(reflectance(400.) > 1e-3);
+    assert!(reflectance(400.) < fresnel_dielectric(1., substrate));
+  }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::HitRecord;
+use crate::algorithm::medium::{DoubleHenyeyGreenstein, HenyeyGreenstein, PhaseFunction};
+use crate::algorithm::ray::Ray;
+use crate::algorithm::texture::{SolidColor, Texture};
+use crate::algorithm::vec3::Vec3;
+
+use super::Material;
+
+pub struct Volume {
+  pub albedo: Arc<dyn Texture>,
+  pub phase_function: Arc<dyn PhaseFunction>,
+}
+
+impl Volume {
+  pub fn new(albedo: Color, phase_function: Arc<dyn PhaseFunction>) -> Volume {
+    Self::with_texture(Arc::new(SolidColor::new(albedo)), phase_function)
+  }
+
+  pub fn with_texture(albedo: Arc<dyn Texture>, phase_function: Arc<dyn PhaseFunction>) -> Volume {
+    Volume {
+      albedo,
+      phase_function,
+    }
+  }
+
+  pub fn henyey_greenstein(albedo: Color, g: f64) -> Volume {
+    Self::new(albedo, Arc::new(HenyeyGreenstein::new(g)))
+  }
+
+  pub fn cloud(albedo: Color) -> Volume {
+    Self::new(albedo, Arc::new(DoubleHenyeyGreenstein::cloud()))
+  }
+}
+
+impl Material for Volume {
+  fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Ray, Color)> {
+    let direction = self.phase_function.sample(ray_in.direction);
+    let scattered = Ray::new(hit_record.point, direction);
+    let attenuation = self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    Some((scattered, attenuation))
+  }
+
+  fn eval(&self, ray_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Color {
+    let albedo = self
+      .albedo
+      .value(hit_record.u, hit_record.v, hit_record.point);
+    self.phase_function.p(ray_in.direction, direction) * albedo
+  }
+
+  fn pdf(&self, ray_in: &Ray, _: &HitRecord, direction: Vec3) -> f64 {
+    self.phase_function.pdf(ray_in.direction, direction)
+  }
+}
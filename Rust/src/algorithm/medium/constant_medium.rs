@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::{HitRecord, Hittable};
+use crate::algorithm::interval::Interval;
+use crate::algorithm::material::{Isotropic, Material, Volume};
+use crate::algorithm::medium::PhaseFunction;
+use crate::algorithm::random;
+use crate::algorithm::ray::Ray;
+
+pub struct ConstantMedium {
+  pub boundary: Arc<dyn Hittable>,
+  pub phase_function: Arc<dyn Material>,
+  neg_inv_density: f64,
+}
+
+impl ConstantMedium {
+  pub fn new(boundary: Arc<dyn Hittable>, density: f64, albedo: Color) -> ConstantMedium {
+    Self::with_material(boundary, density, Arc::new(Isotropic::new(albedo)))
+  }
+
+  pub fn with_phase(
+    boundary: Arc<dyn Hittable>,
+    density: f64,
+    albedo: Color,
+    phase_function: Arc<dyn PhaseFunction>,
+  ) -> ConstantMedium {
+    Self::with_material(boundary, density, Arc::new(Volume::new(albedo, phase_function)))
+  }
+
+  pub fn with_material(
+    boundary: Arc<dyn Hittable>,
+    density: f64,
+    phase_function: Arc<dyn Material>,
+  ) -> ConstantMedium {
+    ConstantMedium {
+      boundary,
+      phase_function,
+      neg_inv_density: -1. / density,
+    }
+  }
+}
+
+impl Hittable for ConstantMedium {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    let entry = self.boundary.hit(ray, Interval::universe())?;
+    let exit = self
+      .boundary
+      .hit(ray, Interval::new(entry.t + 0.0001, f64::INFINITY))?;
+
+    let t_min = entry.t.max(interval.min).max(0.);
+    let t_max = exit.t.min(interval.max);
+    if t_min >= t_max {
+      return None;
+    }
+
+    let ray_length = ray.direction.len();
+    let distance_inside_boundary = (t_max - t_min) * ray_length;
+    let hit_distance = self.neg_inv_density * random::rng().random::<f64>().ln();
+    if hit_distance > distance_inside_boundary {
+      return None;
+    }
+
+    let t = t_min + hit_distance / ray_length;
+    let normal = -ray.direction.normalization();
+    Some(HitRecord::new(ray.at(t), t, normal, ray, self.phase_function.as_ref()))
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    self.boundary.bounding_box()
+  }
+}
@@ -0,0 +1,330 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::algorithm::aabb::Aabb;
+use crate::algorithm::color::Color;
+use crate::algorithm::hittable::{HitRecord, Hittable};
+use crate::algorithm::interval::Interval;
+use crate::algorithm::material::{Isotropic, Material, Volume};
+use crate::algorithm::medium::PhaseFunction;
+use crate::algorithm::perlin::Perlin;
+use crate::algorithm::random;
+use crate::algorithm::ray::Ray;
+use crate::algorithm::vec3::Vec3;
+
+pub trait Density: Send + Sync {
+  fn density(&self, local: Vec3) -> f64;
+  fn max_density(&self) -> f64;
+}
+
+pub struct VoxelGrid {
+  pub width: usize,
+  pub height: usize,
+  pub depth: usize,
+  values: Vec<f32>,
+  max_value: f64,
+}
+
+impl VoxelGrid {
+  pub fn new(width: usize, height: usize, depth: usize, values: Vec<f32>) -> io::Result<VoxelGrid> {
+    let count = width.checked_mul(height).and_then(|n| n.checked_mul(depth));
+    if count != Some(values.len()) {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "voxel count does not match dimensions",
+      ));
+    }
+    Self::from_values(width, height, depth, values)
+  }
+
+  fn from_values(
+    width: usize,
+    height: usize,
+    depth: usize,
+    values: Vec<f32>,
+  ) -> io::Result<VoxelGrid> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if values.is_empty() {
+      return Err(invalid("voxel grid is empty"));
+    }
+    if values.iter().any(|v| !v.is_finite() || *v < 0.) {
+      return Err(invalid("voxel densities must be finite and non-negative"));
+    }
+    let max_value = values.iter().fold(0f32, |max, value| max.max(*value)) as f64;
+    Ok(VoxelGrid {
+      width,
+      height,
+      depth,
+      values,
+      max_value,
+    })
+  }
+
+  pub fn from_fn<F>(width: usize, height: usize, depth: usize, f: F) -> io::Result<VoxelGrid>
+  where
+    F: Fn(Vec3) -> f64,
+  {
+    let mut values = Vec::with_capacity(width * height * depth);
+    for z in 0..depth {
+      for y in 0..height {
+        for x in 0..width {
+          let local = Vec3::new(
+            (x as f64 + 0.5) / width as f64,
+            (y as f64 + 0.5) / height as f64,
+            (z as f64 + 0.5) / depth as f64,
+          );
+          values.push(f(local) as f32);
+        }
+      }
+    }
+    Self::from_values(width, height, depth, values)
+  }
+
+  pub fn load_raw<P: AsRef<Path>>(path: P) -> io::Result<VoxelGrid> {
+    Self::parse_raw(&fs::read(path)?)
+  }
+
+  pub fn parse_raw(bytes: &[u8]) -> io::Result<VoxelGrid> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if bytes.len() < 12 {
+      return Err(invalid("voxel header is truncated"));
+    }
+    let dimension =
+      |i: usize| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap()) as usize;
+    let (width, height, depth) = (dimension(0), dimension(1), dimension(2));
+    let count = width
+      .checked_mul(height)
+      .and_then(|n| n.checked_mul(depth))
+      .and_then(|n| n.checked_mul(4))
+      .ok_or_else(|| invalid("voxel dimensions overflow"))?;
+    let data = &bytes[12..];
+    if data.len() != count {
+      return Err(invalid("voxel data does not match dimensions"));
+    }
+    let values = data
+      .chunks_exact(4)
+      .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+      .collect();
+    Self::from_values(width, height, depth, values)
+  }
+
+  pub fn value(&self, x: usize, y: usize, z: usize) -> f64 {
+    self.values[(z * self.height + y) * self.width + x] as f64
+  }
+
+  fn lookup(&self, x: f64, y: f64, z: f64) -> f64 {
+    let clamp = |v: f64, n: usize| -> (usize, usize, f64) {
+      let v = (v - 0.5).clamp(0., (n - 1) as f64);
+      let i = (v.floor() as usize).min(n - 1);
+      (i, (i + 1).min(n - 1), v - i as f64)
+    };
+    let (x0, x1, fx) = clamp(x, self.width);
+    let (y0, y1, fy) = clamp(y, self.height);
+    let (z0, z1, fz) = clamp(z, self.depth);
+    let lerp = |a: f64, b: f64, t: f64| (1. - t) * a + t * b;
+    let c00 = lerp(self.value(x0, y0, z0), self.value(x1, y0, z0), fx);
+    let c10 = lerp(self.value(x0, y1, z0), self.value(x1, y1, z0), fx);
+    let c01 = lerp(self.value(x0, y0, z1), self.value(x1, y0, z1), fx);
+    let c11 = lerp(self.value(x0, y1, z1), self.value(x1, y1, z1), fx);
+    lerp(lerp(c00, c10, fy), lerp(c01, c11, fy), fz)
+  }
+}
+
+impl Density for VoxelGrid {
+  fn density(&self, local: Vec3) -> f64 {
+    if self.values.is_empty() {
+      return 0.;
+    }
+    self.lookup(
+      local.x * self.width as f64,
+      local.y * self.height as f64,
+      local.z * self.depth as f64,
+    )
+  }
+
+  fn max_density(&self) -> f64 {
+    self.max_value
+  }
+}
+
+pub struct NoiseDensity {
+  pub noise: Perlin,
+  pub scale: f64,
+  pub octaves: usize,
+  pub density: f64,
+}
+
+impl NoiseDensity {
+  pub fn new(scale: f64, octaves: usize, density: f64) -> NoiseDensity {
+    NoiseDensity {
+      noise: Perlin::new(),
+      scale,
+      octaves,
+      density,
+    }
+  }
+}
+
+impl Density for NoiseDensity {
+  fn density(&self, local: Vec3) -> f64 {
+    let center = local - Vec3::new(0.5, 0.5, 0.5);
+    let falloff = (1. - 2. * center.len()).max(0.);
+    let turbulence = self.noise.turbulence(self.scale * local, self.octaves);
+    self.density * (falloff * 2. * turbulence).min(1.)
+  }
+
+  fn max_density(&self) -> f64 {
+    self.density
+  }
+}
+
+pub struct GridMedium {
+  pub bounds: Aabb,
+  pub density: Arc<dyn Density>,
+  pub sigma_t: f64,
+  pub phase_function: Arc<dyn Material>,
+}
+
+impl GridMedium {
+  pub fn new(bounds: Aabb, density: Arc<dyn Density>, sigma_t: f64, albedo: Color) -> GridMedium {
+    Self::with_material(bounds, density, sigma_t, Arc::new(Isotropic::new(albedo)))
+  }
+
+  pub fn with_phase(
+    bounds: Aabb,
+    density: Arc<dyn Density>,
+    sigma_t: f64,
+    albedo: Color,
+    phase_function: Arc<dyn PhaseFunction>,
+  ) -> GridMedium {
+    Self::with_material(bounds, density, sigma_t, Arc::new(Volume::new(albedo, phase_function)))
+  }
+
+  pub fn with_material(
+    bounds: Aabb,
+    density: Arc<dyn Density>,
+    sigma_t: f64,
+    phase_function: Arc<dyn Material>,
+  ) -> GridMedium {
+    GridMedium {
+      bounds,
+      density,
+      sigma_t,
+      phase_function,
+    }
+  }
+
+  fn extinction(&self, point: Vec3) -> f64 {
+    self.sigma_t * self.density.density(self.bounds.local(point))
+  }
+
+  fn majorant(&self) -> f64 {
+    self.sigma_t * self.density.max_density()
+  }
+}
+
+impl Hittable for GridMedium {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    let (t_min, t_max) = self.bounds.hit(ray, interval)?;
+    let majorant = self.majorant();
+    if majorant <= 0. {
+      return None;
+    }
+
+    let ray_length = ray.direction.len();
+    let mut rng = random::rng();
+    let mut t = t_min;
+    loop {
+      t -= (1. - rng.random::<f64>()).ln() / (majorant * ray_length);
+      if t >= t_max {
+        return None;
+      }
+      let point = ray.at(t);
+      if rng.random::<f64>() * majorant < self.extinction(point) {
+        let normal = -ray.direction.normalization();
+        return Some(HitRecord::new(point, t, normal, ray, self.phase_function.as_ref()));
+      }
+    }
+  }
+
+  fn transmittance(&self, ray: Ray, interval: Interval) -> f64 {
+    let Some((t_min, t_max)) = self.bounds.hit(ray, interval) else {
+      return 1.;
+    };
+    let majorant = self.majorant();
+    if majorant <= 0. {
+      return 1.;
+    }
+    let ray_length = ray.direction.len();
+    let mut rng = random::rng();
+    let mut transmittance = 1.;
+    let mut t = t_min;
+    loop {
+      t -= (1. - rng.random::<f64>()).ln() / (majorant * ray_length);
+      if t >= t_max {
+        return transmittance;
+      }
+      transmittance *= 1. - self.extinction(ray.at(t)) / majorant;
+      if transmittance <= 0. {
+        return 0.;
+      }
+    }
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    Some(self.bounds)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn raw(dimensions: [u32; 3], values: &[f32]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = dimensions.iter().flat_map(|d| d.to_le_bytes()).collect();
+    bytes.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+    bytes
+  }
+
+  #[test]
+  fn parses_raw_grids() {
+    let grid = VoxelGrid::parse_raw(&raw([2, 1, 1], &[0.25, 0.5])).unwrap();
+    assert_eq!((grid.width, grid.height, grid.depth), (2, 1, 1));
+    assert_eq!(grid.value(1, 0, 0), 0.5);
+    assert_eq!(grid.max_density(), 0.5);
+  }
+
+  #[test]
+  fn rejects_malformed_raw_grids() {
+    let invalid = |bytes: &[u8]| VoxelGrid::parse_raw(bytes).err().map(|error| error.kind());
+    assert_eq!(invalid(&[0; 8]), Some(io::ErrorKind::InvalidData));
+    assert_eq!(invalid(&raw([2, 2, 1], &[1.; 3])), Some(io::ErrorKind::InvalidData));
+    assert_eq!(invalid(&raw([u32::MAX; 3], &[])), Some(io::ErrorKind::InvalidData));
+    assert_eq!(invalid(&raw([1 << 31, 1 << 31, 1], &[])), Some(io::ErrorKind::InvalidData));
+    assert!(VoxelGrid::new(2, 2, 2, vec![0.; 7]).is_err());
+    assert_eq!(invalid(&raw([0, 0, 0], &[])), Some(io::ErrorKind::InvalidData));
+    for value in [f32::NAN, f32::INFINITY, -1.] {
+      assert_eq!(invalid(&raw([2, 1, 1], &[0.5, value])), Some(io::ErrorKind::InvalidData));
+      assert!(VoxelGrid::new(2, 1, 1, vec![value, 0.5]).is_err());
+      assert!(VoxelGrid::from_fn(2, 1, 1, |_| value as f64).is_err());
+    }
+  }
+
+  #[test]
+  fn ratio_tracking_matches_beer_lambert() {
+    let grid = VoxelGrid::from_fn(4, 4, 4, |_| 1.).unwrap();
+    let bounds = Aabb::new(Vec3::zero(), Vec3::one());
+    let medium = GridMedium::new(bounds, Arc::new(grid), 1.5, Color::one());
+    let ray = Ray::new(Vec3::new(0.5, 0.5, -1.), Vec3::new(0., 0., 1.));
+    let samples = 20000;
+    let mean = (0..samples)
+      .map(|_| medium.transmittance(ray, Interval::new(0., f64::INFINITY)))
+      .sum::<f64>()
+      / samples as f64;
+    assert!((mean - (-1.5f64).exp()).abs() < 0.01);
+  }
+}
@@ -0,0 +1,49 @@
+use rand::Rng;
+
+use super::color::Color;
+use super::random;
+use super::vec3::Vec3;
+
+mod constant_medium;
+mod grid_medium;
+mod phase;
+
+pub use constant_medium::ConstantMedium;
+pub use grid_medium::{Density, GridMedium, NoiseDensity, VoxelGrid};
+pub use phase::{DoubleHenyeyGreenstein, HenyeyGreenstein, IsotropicPhase, PhaseFunction};
+
+#[derive(Debug, Clone, Copy)]
+pub struct FreeFlight {
+  pub distance: f64,
+  pub scattered: bool,
+  pub weight: Color,
+}
+
+pub fn sample_free_flight(sigma_t: Color, sigma_s: Color, max_distance: f64) -> FreeFlight {
+  let mut rng = random::rng();
+  let channels = [sigma_t.x, sigma_t.y, sigma_t.z];
+  let sigma = channels[rng.random_range(0..3)];
+  let distance = if sigma > 0. {
+    -(1. - rng.random::<f64>()).ln() / sigma
+  } else {
+    f64::INFINITY
+  };
+
+  if distance < max_distance {
+    let transmittance = (-distance * sigma_t).exp();
+    let pdf = (sigma_t * transmittance).dot(Vec3::one()) / 3.;
+    FreeFlight {
+      distance,
+      scattered: true,
+      weight: sigma_s * transmittance / pdf,
+    }
+  } else {
+    let transmittance = (-max_distance * sigma_t).exp();
+    let pdf = transmittance.dot(Vec3::one()) / 3.;
+    FreeFlight {
+      distance: max_distance,
+      scattered: false,
+      weight: transmittance / pdf,
+    }
+  }
+}
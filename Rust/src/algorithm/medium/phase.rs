@@ -0,0 +1,189 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::algorithm::onb::Onb;
+use crate::algorithm::random;
+use crate::algorithm::vec3::Vec3;
+
+pub trait PhaseFunction: Send + Sync {
+  fn p(&self, direction: Vec3, scattered: Vec3) -> f64;
+
+  fn sample(&self, direction: Vec3) -> Vec3;
+
+  fn pdf(&self, direction: Vec3, scattered: Vec3) -> f64 {
+    self.p(direction, scattered)
+  }
+}
+
+impl<P> PhaseFunction for Arc<P>
+where
+  P: PhaseFunction + ?Sized,
+{
+  fn p(&self, direction: Vec3, scattered: Vec3) -> f64 {
+    (**self).p(direction, scattered)
+  }
+
+  fn sample(&self, direction: Vec3) -> Vec3 {
+    (**self).sample(direction)
+  }
+
+  fn pdf(&self, direction: Vec3, scattered: Vec3) -> f64 {
+    (**self).pdf(direction, scattered)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IsotropicPhase;
+
+impl PhaseFunction for IsotropicPhase {
+  fn p(&self, _: Vec3, _: Vec3) -> f64 {
+    1. / (4. * PI)
+  }
+
+  fn sample(&self, _: Vec3) -> Vec3 {
+    Vec3::random_unit_vector()
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HenyeyGreenstein {
+  pub g: f64,
+}
+
+impl HenyeyGreenstein {
+  pub fn new(g: f64) -> HenyeyGreenstein {
+    HenyeyGreenstein {
+      g: g.clamp(-0.99, 0.99),
+    }
+  }
+
+  pub fn evaluate(g: f64, cos_theta: f64) -> f64 {
+    let denominator = 1. + g * g - 2. * g * cos_theta;
+    (1. - g * g) / (4. * PI * denominator * denominator.max(1e-12).sqrt())
+  }
+
+  pub fn sample_direction(direction: Vec3, g: f64) -> Vec3 {
+    let mut rng = random::rng();
+    let (u1, u2) = (rng.random::<f64>(), rng.random::<f64>());
+    let cos_theta = if g.abs() < 1e-3 {
+      1. - 2. * u1
+    } else {
+      let square = (1. - g * g) / (1. - g + 2. * g * u1);
+      ((1. + g * g - square * square) / (2. * g)).clamp(-1., 1.)
+    };
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * u2;
+    let frame = Onb::new(direction.normalization());
+    frame.transform(Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta))
+  }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+  fn p(&self, direction: Vec3, scattered: Vec3) -> f64 {
+    let cos_theta = direction.normalization().dot(scattered.normalization());
+    Self::evaluate(self.g, cos_theta)
+  }
+
+  fn sample(&self, direction: Vec3) -> Vec3 {
+    Self::sample_direction(direction, self.g)
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DoubleHenyeyGreenstein {
+  pub forward: f64,
+  pub backward: f64,
+  pub weight: f64,
+}
+
+impl DoubleHenyeyGreenstein {
+  pub fn new(forward: f64, backward: f64, weight: f64) -> DoubleHenyeyGreenstein {
+    DoubleHenyeyGreenstein {
+      forward: forward.clamp(-0.99, 0.99),
+      backward: backward.clamp(-0.99, 0.99),
+      weight: weight.clamp(0., 1.),
+    }
+  }
+
+  pub fn cloud() -> DoubleHenyeyGreenstein {
+    Self::new(0.8, -0.3, 0.85)
+  }
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein {
+  fn p(&self, direction: Vec3, scattered: Vec3) -> f64 {
+    let cos_theta = direction.normalization().dot(scattered.normalization());
+    self.weight * HenyeyGreenstein::evaluate(self.forward, cos_theta)
+      + (1. - self.weight) * HenyeyGreenstein::evaluate(self.backward, cos_theta)
+  }
+
+  fn sample(&self, direction: Vec3) -> Vec3 {
+    let g = if random::rng().random::<f64>() < self.weight {
+      self.forward
+    } else {
+      self.backward
+    };
+    HenyeyGreenstein::sample_direction(direction, g)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn integrate_cosine(f: impl Fn(f64) -> f64) -> f64 {
+    let steps = 100000;
+    let step = 2. / steps as f64;
+    (0..steps)
+      .map(|i| 2. * PI * f(-1. + (i as f64 + 0.5) * step) * step)
+      .sum()
+  }
+
+  #[test]
+  fn henyey_greenstein_is_normalized() {
+    for g in [-0.8, 0., 0.8] {
+      let total = integrate_cosine(|cos_theta| HenyeyGreenstein::evaluate(g, cos_theta));
+      assert!((total - 1.).abs() < 1e-3, "{g}: {total}");
+      let mean = integrate_cosine(|cos_theta| cos_theta * HenyeyGreenstein::evaluate(g, cos_theta));
+      assert!((mean - g).abs() < 1e-3, "{g}: {mean}");
+    }
+  }
+
+  #[test]
+  fn sampled_directions_follow_the_phase_function() {
+    let direction = Vec3::new(0.3, -0.2, 0.9).normalization();
+    let phases: [Box<dyn PhaseFunction>; 4] = [
+      Box::new(HenyeyGreenstein::new(-0.8)),
+      Box::new(HenyeyGreenstein::new(0.)),
+      Box::new(HenyeyGreenstein::new(0.8)),
+      Box::new(DoubleHenyeyGreenstein::cloud()),
+    ];
+    let bins = 10;
+    let samples = 100000;
+    for phase in phases {
+      let mut histogram = vec![0usize; bins];
+      for _ in 0..samples {
+        let cos_theta = direction.dot(phase.sample(direction).normalization());
+        let bin = ((cos_theta + 1.) / 2. * bins as f64) as usize;
+        histogram[bin.min(bins - 1)] += 1;
+      }
+      for (bin, count) in histogram.into_iter().enumerate() {
+        let (low, high) =
+          (-1. + 2. * bin as f64 / bins as f64, -1. + 2. * (bin + 1) as f64 / bins as f64);
+        let expected = integrate_cosine(|cos_theta| {
+          if (low..high).contains(&cos_theta) {
+            let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+            let scattered = Onb::new(direction).transform(Vec3::new(sin_theta, 0., cos_theta));
+            phase.pdf(direction, scattered)
+          } else {
+            0.
+          }
+        });
+        let sampled = count as f64 / samples as f64;
+        assert!((sampled - expected).abs() < 0.006, "bin {bin}: {sampled} {expected}");
+      }
+    }
+  }
+}
@@ -1,11 +1,31 @@
+pub mod aabb;
+pub mod alpha_mask;
+pub mod aperture;
+pub mod background;
 pub mod camera;
 pub mod color;
+pub mod complex;
 pub mod constant;
+pub mod direction_cone;
+pub mod distribution;
+pub mod environment;
 pub mod generator;
 pub mod hittable;
 pub mod hittable_list;
 pub mod interval;
+pub mod lens;
+pub mod light;
+pub mod loader;
 pub mod material;
+pub mod medium;
+pub mod onb;
+pub mod perlin;
+pub mod quad;
+pub mod random;
 pub mod ray;
+pub mod sky;
+pub mod spectrum;
 pub mod sphere;
+pub mod texture;
+pub mod triangle;
 pub mod vec3;
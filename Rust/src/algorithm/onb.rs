@@ -0,0 +1,28 @@
+use super::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+  pub u: Vec3,
+  pub v: Vec3,
+  pub w: Vec3,
+}
+
+impl Onb {
+  pub fn new(normal: Vec3) -> Onb {
+    let w = normal.normalization();
+    let sign = 1f64.copysign(w.z);
+    let a = -1. / (sign + w.z);
+    let b = w.x * w.y * a;
+    let u = Vec3::new(1. + sign * w.x * w.x * a, sign * b, -sign * w.x);
+    let v = Vec3::new(b, sign + w.y * w.y * a, -w.y);
+    Onb { u, v, w }
+  }
+
+  pub fn transform(&self, local: Vec3) -> Vec3 {
+    local.x * self.u + local.y * self.v + local.z * self.w
+  }
+
+  pub fn to_local(&self, world: Vec3) -> Vec3 {
+    Vec3::new(world.dot(self.u), world.dot(self.v), world.dot(self.w))
+  }
+}
@@ -0,0 +1,93 @@
+use rand::seq::SliceRandom;
+
+use super::random;
+use super::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+pub struct Perlin {
+  random_vectors: Vec<Vec3>,
+  perm_x: Vec<usize>,
+  perm_y: Vec<usize>,
+  perm_z: Vec<usize>,
+}
+
+impl Default for Perlin {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Perlin {
+  pub fn new() -> Perlin {
+    let random_vectors = (0..POINT_COUNT)
+      .map(|_| Vec3::random_range(-1., 1.).normalization())
+      .collect();
+    Perlin {
+      random_vectors,
+      perm_x: Self::generate_perm(),
+      perm_y: Self::generate_perm(),
+      perm_z: Self::generate_perm(),
+    }
+  }
+
+  pub fn noise(&self, point: Vec3) -> f64 {
+    let u = point.x - point.x.floor();
+    let v = point.y - point.y.floor();
+    let w = point.z - point.z.floor();
+    let i = point.x.floor() as i64;
+    let j = point.y.floor() as i64;
+    let k = point.z.floor() as i64;
+
+    let mut c = [[[Vec3::zero(); 2]; 2]; 2];
+    for (di, plane) in c.iter_mut().enumerate() {
+      for (dj, row) in plane.iter_mut().enumerate() {
+        for (dk, corner) in row.iter_mut().enumerate() {
+          let index = self.perm_x[((i + di as i64) & 255) as usize]
+            ^ self.perm_y[((j + dj as i64) & 255) as usize]
+            ^ self.perm_z[((k + dk as i64) & 255) as usize];
+          *corner = self.random_vectors[index];
+        }
+      }
+    }
+    Self::perlin_interpolation(&c, u, v, w)
+  }
+
+  pub fn turbulence(&self, point: Vec3, depth: usize) -> f64 {
+    let mut accumulation = 0.;
+    let mut temp_point = point;
+    let mut weight = 1.;
+    for _ in 0..depth {
+      accumulation += weight * self.noise(temp_point);
+      weight *= 0.5;
+      temp_point *= 2.;
+    }
+    accumulation.abs()
+  }
+
+  fn generate_perm() -> Vec<usize> {
+    let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+    perm.shuffle(&mut random::rng());
+    perm
+  }
+
+  fn perlin_interpolation(c: &[[[Vec3; 2]; 2]; 2], u: f64, v: f64, w: f64) -> f64 {
+    let uu = u * u * (3. - 2. * u);
+    let vv = v * v * (3. - 2. * v);
+    let ww = w * w * (3. - 2. * w);
+    let mut accumulation = 0.;
+    for (i, plane) in c.iter().enumerate() {
+      for (j, row) in plane.iter().enumerate() {
+        for (k, corner) in row.iter().enumerate() {
+          let (fi, fj, fk) = (i as f64, j as f64, k as f64);
+          let weight = Vec3::new(u - fi, v - fj, w - fk);
+          accumulation += (fi * uu + (1. - fi) * (1. - uu))
+            * (fj * vv + (1. - fj) * (1. - vv))
+            * (fk * ww + (1. - fk) * (1. - ww))
+            * corner.dot(weight);
+        }
+      }
+    }
+    accumulation
+  }
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::aabb::Aabb;
+use super::color::Color;
+use super::direction_cone::DirectionCone;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::material::Material;
+use super::random;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Clone)]
+pub struct Quad {
+  pub q: Vec3,
+  pub u: Vec3,
+  pub v: Vec3,
+  pub material: Arc<dyn Material>,
+  w: Vec3,
+  normal: Vec3,
+  d: f64,
+  area: f64,
+}
+
+impl Quad {
+  pub fn new(q: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Quad {
+    let n = Vec3::cross(u, v);
+    let normal = n.normalization();
+    Quad {
+      q,
+      u,
+      v,
+      material,
+      w: n / n.dot(n),
+      normal,
+      d: normal.dot(q),
+      area: n.len(),
+    }
+  }
+}
+
+impl Hittable for Quad {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    let denominator = self.normal.dot(ray.direction);
+    if denominator.abs() < 1e-8 {
+      return None;
+    }
+    let t = (self.d - self.normal.dot(ray.origin)) / denominator;
+    if !interval.surrounds(t) {
+      return None;
+    }
+
+    let intersection = ray.at(t);
+    let planar_hit_point = intersection - self.q;
+    let alpha = self.w.dot(Vec3::cross(planar_hit_point, self.v));
+    let beta = self.w.dot(Vec3::cross(self.u, planar_hit_point));
+    if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+      return None;
+    }
+
+    let mut record = HitRecord::new(intersection, t, self.normal, ray, self.material.as_ref());
+    (record.u, record.v) = (alpha, beta);
+    record.set_tangent(self.u);
+    Some(record)
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    let Some(record) = self.hit(Ray::new(origin, direction), Interval::new(0.001, f64::INFINITY))
+    else {
+      return 0.;
+    };
+    let distance_squared = record.t * record.t * direction.len_squared();
+    let cosine = (direction.dot(self.normal) / direction.len()).abs();
+    distance_squared / (cosine * self.area)
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    let mut rng = random::rng();
+    let point = self.q + rng.random::<f64>() * self.u + rng.random::<f64>() * self.v;
+    point - origin
+  }
+
+  fn area(&self) -> f64 {
+    self.area
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission()
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    let diagonal = Aabb::new(self.q, self.q + self.u + self.v);
+    let other = Aabb::new(self.q + self.u, self.q + self.v);
+    Some(diagonal.union(&other).pad(1e-4))
+  }
+
+  fn normal_bounds(&self) -> DirectionCone {
+    DirectionCone::new(self.normal, 1.)
+  }
+}
@@ -0,0 +1,37 @@
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+thread_local! {
+  static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_os_rng());
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalRng;
+
+impl RngCore for LocalRng {
+  fn next_u32(&mut self) -> u32 {
+    RNG.with(|rng| rng.borrow_mut().next_u32())
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    RNG.with(|rng| rng.borrow_mut().next_u64())
+  }
+
+  fn fill_bytes(&mut self, dst: &mut [u8]) {
+    RNG.with(|rng| rng.borrow_mut().fill_bytes(dst))
+  }
+}
+
+pub fn rng() -> LocalRng {
+  LocalRng
+}
+
+pub fn seed(state: u64) {
+  RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(state));
+}
+
+pub fn reseed() {
+  RNG.with(|rng| *rng.borrow_mut() = StdRng::from_os_rng());
+}
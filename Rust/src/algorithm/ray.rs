@@ -1,14 +1,28 @@
+use super::spectrum::Wavelengths;
 use super::vec3::Vec3;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Ray {
   pub origin: Vec3,
   pub direction: Vec3,
+  pub wavelengths: Option<Wavelengths>,
 }
 
 impl Ray {
   pub fn new(origin: Vec3, direction: Vec3) -> Self {
-    Self { origin, direction }
+    Self {
+      origin,
+      direction,
+      wavelengths: None,
+    }
+  }
+
+  pub fn with_wavelengths(origin: Vec3, direction: Vec3, wavelengths: Wavelengths) -> Self {
+    Self {
+      origin,
+      direction,
+      wavelengths: Some(wavelengths),
+    }
   }
 
   pub fn at(&self, t: f64) -> Vec3 {
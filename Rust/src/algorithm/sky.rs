@@ -0,0 +1,189 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use super::background::Background;
+use super::color::{Color, luminance};
+use super::light::{DirectionalLight, Light, LightSample};
+use super::onb::Onb;
+use super::random;
+use super::ray::Ray;
+use super::spectrum::xyz_to_unbalanced_srgb;
+use super::vec3::Vec3;
+
+const SUN_ILLUMINANCE: f64 = 127.5;
+const CIRCUMSOLAR_COS: f64 = 0.94;
+const CIRCUMSOLAR_PROBABILITY: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy)]
+struct Perez {
+  a: f64,
+  b: f64,
+  c: f64,
+  d: f64,
+  e: f64,
+}
+
+impl Perez {
+  fn f(&self, cos_theta: f64, gamma: f64) -> f64 {
+    (1. + self.a * (self.b / cos_theta.max(1e-3)).exp())
+      * (1. + self.c * (self.d * gamma).exp() + self.e * gamma.cos().powi(2))
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct PhysicalSky {
+  pub sun_direction: Vec3,
+  pub turbidity: f64,
+  pub scale: f64,
+  perez: [Perez; 3],
+  zenith: [f64; 3],
+}
+
+impl PhysicalSky {
+  pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> PhysicalSky {
+    let (elevation, azimuth) = (elevation.to_radians(), azimuth.to_radians());
+    let sun_direction =
+      Vec3::new(elevation.cos() * azimuth.sin(), elevation.sin(), -elevation.cos() * azimuth.cos());
+    let t = turbidity.max(1.);
+    let theta = (PI / 2. - elevation).clamp(0., PI / 2.);
+
+    let perez = [
+      Perez {
+        a: 0.1787 * t - 1.4630,
+        b: -0.3554 * t + 0.4275,
+        c: -0.0227 * t + 5.3251,
+        d: 0.1206 * t - 2.5771,
+        e: -0.0670 * t + 0.3703,
+      },
+      Perez {
+        a: -0.0193 * t - 0.2592,
+        b: -0.0665 * t + 0.0008,
+        c: -0.0004 * t + 0.2125,
+        d: -0.0641 * t - 0.8989,
+        e: -0.0033 * t + 0.0452,
+      },
+      Perez {
+        a: -0.0167 * t - 0.2608,
+        b: -0.0950 * t + 0.0092,
+        c: -0.0079 * t + 0.2102,
+        d: -0.0441 * t - 1.6537,
+        e: -0.0109 * t + 0.0529,
+      },
+    ];
+
+    let chi = (4. / 9. - t / 120.) * (PI - 2. * theta);
+    let luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.);
+    let (t2, th, th2, th3) = (t * t, theta, theta * theta, theta * theta * theta);
+    let x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+      + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+      + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+    let y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+      + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+      + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+    let zenith = [luminance, x, y];
+    let normalized = [0, 1, 2].map(|i| zenith[i] / perez[i].f(1., theta));
+    PhysicalSky {
+      sun_direction,
+      turbidity: t,
+      scale: 0.1,
+      perez,
+      zenith: normalized,
+    }
+  }
+
+  pub fn radiance(&self, direction: Vec3) -> Color {
+    let direction = direction.normalization();
+    if direction.y <= 0. {
+      return Color::zero();
+    }
+    let gamma = direction.dot(self.sun_direction).clamp(-1., 1.).acos();
+    let [luminance, x, y] = [0, 1, 2].map(|i| self.zenith[i] * self.perez[i].f(direction.y, gamma));
+    if y <= 0. {
+      return Color::zero();
+    }
+    let xyz = Vec3::new(x / y * luminance, luminance, (1. - x - y) / y * luminance);
+    let rgb = self.scale * xyz_to_unbalanced_srgb(xyz);
+    Color::new(rgb.x.max(0.), rgb.y.max(0.), rgb.z.max(0.))
+  }
+
+  pub fn sun(&self) -> DirectionalLight {
+    let cos_theta = self.sun_direction.y;
+    if cos_theta <= 0. {
+      return DirectionalLight::sun(self.sun_direction, Color::zero());
+    }
+    let theta = cos_theta.acos().to_degrees();
+    let air_mass = 1. / (cos_theta + 0.15 * (93.885 - theta).powf(-1.253));
+    let beta = 0.04608 * self.turbidity - 0.04586;
+    let transmittance = |lambda: f64| {
+      let rayleigh = (-0.008735 * lambda.powf(-4.08) * air_mass).exp();
+      let aerosol = (-beta * lambda.powf(-1.3) * air_mass).exp();
+      rayleigh * aerosol
+    };
+    let irradiance = self.scale
+      * SUN_ILLUMINANCE
+      * Color::new(transmittance(0.65), transmittance(0.55), transmittance(0.45));
+    DirectionalLight::sun(self.sun_direction, irradiance)
+  }
+
+  fn circumsolar(&self) -> bool {
+    self.sun_direction.y > 0.
+  }
+}
+
+impl Background for PhysicalSky {
+  fn color(&self, ray: &Ray) -> Color {
+    self.radiance(ray.direction)
+  }
+}
+
+impl Light for PhysicalSky {
+  fn sample(&self, point: Vec3) -> Option<LightSample> {
+    let mut rng = random::rng();
+    let direction = if self.circumsolar() && rng.random::<f64>() < CIRCUMSOLAR_PROBABILITY {
+      Onb::new(self.sun_direction).transform(Vec3::random_in_cone(CIRCUMSOLAR_COS))
+    } else {
+      Onb::new(Vec3::new(0., 1., 0.)).transform(Vec3::random_cosine_direction())
+    };
+    let pdf = self.pdf(point, direction);
+    if pdf <= 0. {
+      return None;
+    }
+    Some(LightSample {
+      direction,
+      distance: f64::INFINITY,
+      radiance: self.radiance(direction),
+      pdf,
+    })
+  }
+
+  fn pdf(&self, _point: Vec3, direction: Vec3) -> f64 {
+    let direction = direction.normalization();
+    if direction.y <= 0. {
+      return 0.;
+    }
+    let hemisphere = direction.y / PI;
+    if !self.circumsolar() {
+      return hemisphere;
+    }
+    let cone = if direction.dot(self.sun_direction) >= CIRCUMSOLAR_COS {
+      1. / (2. * PI * (1. - CIRCUMSOLAR_COS))
+    } else {
+      0.
+    };
+    CIRCUMSOLAR_PROBABILITY * cone + (1. - CIRCUMSOLAR_PROBABILITY) * hemisphere
+  }
+
+  fn emitted(&self, ray: &Ray) -> Color {
+    self.radiance(ray.direction)
+  }
+
+  fn is_delta(&self) -> bool {
+    false
+  }
+
+  fn power(&self) -> f64 {
+    PI * luminance(self.radiance(Vec3::new(0., 1., 0.)))
+  }
+}
@@ -0,0 +1,129 @@
+use std::sync::OnceLock;
+
+use super::color::Color;
+use super::vec3::Vec3;
+
+pub const LAMBDA_MIN: f64 = 380.;
+pub const LAMBDA_MAX: f64 = 720.;
+const CIE_Y_INTEGRAL: f64 = 106.856895;
+const INTEGRATION_STEP: f64 = 1.;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Wavelengths {
+  pub lambda: [f64; 3],
+  secondary_terminated: bool,
+}
+
+impl Wavelengths {
+  pub fn sample(u: f64) -> Wavelengths {
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let hero = LAMBDA_MIN + u * range;
+    let rotate = |k: f64| LAMBDA_MIN + (hero - LAMBDA_MIN + k * range / 3.) % range;
+    Wavelengths {
+      lambda: [hero, rotate(1.), rotate(2.)],
+      secondary_terminated: false,
+    }
+  }
+
+  pub fn pdf(&self) -> f64 {
+    1. / (LAMBDA_MAX - LAMBDA_MIN)
+  }
+
+  pub fn hero(&self) -> f64 {
+    self.lambda[0]
+  }
+
+  pub fn secondary_terminated(&self) -> bool {
+    self.secondary_terminated
+  }
+
+  pub fn terminate_secondary(mut self) -> Wavelengths {
+    self.secondary_terminated = true;
+    self
+  }
+
+  pub fn to_xyz(&self, radiance: Color) -> Vec3 {
+    let values = [radiance.x, radiance.y, radiance.z];
+    let mut xyz = Vec3::zero();
+    for (lambda, value) in self.lambda.iter().zip(values) {
+      xyz += value * cie_xyz(*lambda);
+    }
+    xyz / (3. * self.pdf() * CIE_Y_INTEGRAL)
+  }
+}
+
+fn gaussian(lambda: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+  let sigma = if lambda < mu { sigma_low } else { sigma_high };
+  (-0.5 * ((lambda - mu) / sigma).powi(2)).exp()
+}
+
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+  Vec3::new(
+    1.056 * gaussian(lambda, 599.8, 37.9, 31.0) + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+      - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2),
+    0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1),
+    1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8),
+  )
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+  let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+  t * t * (3. - 2. * t)
+}
+
+pub fn rgb_to_spectrum(rgb: Color, lambda: f64) -> f64 {
+  let blue = 1. - smoothstep(480., 500., lambda);
+  let red = smoothstep(570., 590., lambda);
+  let green = 1. - blue - red;
+  rgb.x * red + rgb.y * green + rgb.z * blue
+}
+
+pub fn upsample(rgb: Color, wavelengths: &Wavelengths) -> Color {
+  let [l0, l1, l2] = wavelengths.lambda;
+  Color::new(rgb_to_spectrum(rgb, l0), rgb_to_spectrum(rgb, l1), rgb_to_spectrum(rgb, l2))
+}
+
+pub fn xyz_to_unbalanced_srgb(xyz: Vec3) -> Color {
+  Color::new(
+    3.2404542 * xyz.x - 1.5371385 * xyz.y - 0.4985314 * xyz.z,
+    -0.9692660 * xyz.x + 1.8760108 * xyz.y + 0.0415560 * xyz.z,
+    0.0556434 * xyz.x - 0.2040259 * xyz.y + 1.0572252 * xyz.z,
+  )
+}
+
+fn white_balance() -> Color {
+  static WHITE: OnceLock<Color> = OnceLock::new();
+  *WHITE.get_or_init(|| {
+    let mut xyz = Vec3::zero();
+    let mut lambda = LAMBDA_MIN;
+    while lambda < LAMBDA_MAX {
+      xyz += INTEGRATION_STEP * cie_xyz(lambda + 0.5 * INTEGRATION_STEP);
+      lambda += INTEGRATION_STEP;
+    }
+    xyz_to_unbalanced_srgb(xyz / CIE_Y_INTEGRAL)
+  })
+}
+
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Color {
+  let rgb = xyz_to_unbalanced_srgb(xyz);
+  let white = white_balance();
+  Color::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn white_round_trips_through_the_spectrum() {
+    let samples = 3000;
+    let white = Color::one();
+    let mut rgb = Color::zero();
+    for i in 0..samples {
+      let wavelengths = Wavelengths::sample((i as f64 + 0.5) / samples as f64);
+      rgb += xyz_to_linear_srgb(wavelengths.to_xyz(upsample(white, &wavelengths)));
+    }
+    let rgb = rgb / samples as f64;
+    assert!((rgb - white).len() < 0.01, "{rgb:?}");
+  }
+}
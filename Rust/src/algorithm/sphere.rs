@@ -1,34 +1,40 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::aabb::Aabb;
+use super::color::Color;
 use super::hittable::{HitRecord, Hittable};
 use super::interval::Interval;
 use super::material::Material;
+use super::onb::Onb;
 use super::ray::Ray;
 use super::vec3::Vec3;
 
-#[derive(Default)]
-pub struct Sphere<M> {
+#[derive(Clone)]
+pub struct Sphere {
   pub center: Vec3,
   pub radius: f64,
-  pub material: M,
+  pub material: Arc<dyn Material>,
 }
 
-impl<M> Sphere<M>
-where
-  M: Material,
-{
-  pub fn new(center: Vec3, radius: f64, material: M) -> Sphere<M> {
+impl Sphere {
+  pub fn new(center: Vec3, radius: f64, material: Arc<dyn Material>) -> Sphere {
     Sphere {
       center,
       radius,
       material,
     }
   }
+
+  fn uv(point: Vec3) -> (f64, f64) {
+    let theta = (-point.y).acos();
+    let phi = (-point.z).atan2(point.x) + PI;
+    (phi / (2. * PI), theta / PI)
+  }
 }
 
-impl<M> Hittable for Sphere<M>
-where
-  M: Material,
-{
-  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord> {
+impl Hittable for Sphere {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
     let oc = self.center - ray.origin;
     let a = ray.direction.len_squared();
     let h = ray.direction.dot(oc);
@@ -48,6 +54,51 @@ where
     }
 
     let point = ray.at(root);
-    Some(HitRecord::new(point, root, (point - self.center) / self.radius, ray, &self.material))
+    let outward_normal = (point - self.center) / self.radius;
+    let mut record = HitRecord::new(point, root, outward_normal, ray, self.material.as_ref());
+    (record.u, record.v) = Self::uv(outward_normal);
+    record.set_tangent(Vec3::new(outward_normal.z, 0., -outward_normal.x));
+    Some(record)
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    let distance_squared = (self.center - origin).len_squared();
+    let radius_squared = self.radius * self.radius;
+    if distance_squared <= radius_squared {
+      return 1. / (4. * PI);
+    }
+    if self
+      .hit(Ray::new(origin, direction), Interval::new(0.001, f64::INFINITY))
+      .is_none()
+    {
+      return 0.;
+    }
+    let cos_theta_max = (1. - radius_squared / distance_squared).sqrt();
+    let solid_angle = 2. * PI * (1. - cos_theta_max);
+    1. / solid_angle
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    let direction = self.center - origin;
+    let distance_squared = direction.len_squared();
+    let radius_squared = self.radius * self.radius;
+    if distance_squared <= radius_squared {
+      return Vec3::random_unit_vector();
+    }
+    let cos_theta_max = (1. - radius_squared / distance_squared).sqrt();
+    Onb::new(direction.normalization()).transform(Vec3::random_in_cone(cos_theta_max))
+  }
+
+  fn area(&self) -> f64 {
+    4. * PI * self.radius * self.radius
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission()
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    let radius = Vec3::new(self.radius, self.radius, self.radius);
+    Some(Aabb::new(self.center - radius, self.center + radius))
   }
 }
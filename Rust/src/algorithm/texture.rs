@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use super::color::Color;
+use super::interval::Interval;
+use super::vec3::Vec3;
+
+pub trait Texture: Send + Sync {
+  fn value(&self, u: f64, v: f64, point: Vec3) -> Color;
+}
+
+pub fn average(texture: &dyn Texture) -> Color {
+  let steps = 16;
+  let mut total = Color::zero();
+  for i in 0..steps {
+    for j in 0..steps {
+      let u = (i as f64 + 0.5) / steps as f64;
+      let v = (j as f64 + 0.5) / steps as f64;
+      total += texture.value(u, v, Vec3::zero());
+    }
+  }
+  total / (steps * steps) as f64
+}
+
+impl<T> Texture for Arc<T>
+where
+  T: Texture + ?Sized,
+{
+  fn value(&self, u: f64, v: f64, point: Vec3) -> Color {
+    (**self).value(u, v, point)
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SolidColor {
+  pub albedo: Color,
+}
+
+impl SolidColor {
+  pub fn new(albedo: Color) -> SolidColor {
+    SolidColor { albedo }
+  }
+}
+
+impl Texture for SolidColor {
+  fn value(&self, _: f64, _: f64, _: Vec3) -> Color {
+    self.albedo
+  }
+}
+
+pub struct CheckerTexture {
+  inv_scale: f64,
+  even: Arc<dyn Texture>,
+  odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+  pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> CheckerTexture {
+    CheckerTexture {
+      inv_scale: 1. / scale,
+      even,
+      odd,
+    }
+  }
+
+  pub fn from_colors(scale: f64, even: Color, odd: Color) -> CheckerTexture {
+    Self::new(scale, Arc::new(SolidColor::new(even)), Arc::new(SolidColor::new(odd)))
+  }
+}
+
+impl Texture for CheckerTexture {
+  fn value(&self, u: f64, v: f64, point: Vec3) -> Color {
+    let x = (self.inv_scale * point.x).floor() as i64;
+    let y = (self.inv_scale * point.y).floor() as i64;
+    let z = (self.inv_scale * point.z).floor() as i64;
+    if (x + y + z) % 2 == 0 {
+      self.even.value(u, v, point)
+    } else {
+      self.odd.value(u, v, point)
+    }
+  }
+}
+
+pub struct ImageTexture {
+  width: usize,
+  height: usize,
+  pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+  pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> ImageTexture {
+    ImageTexture {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  pub fn from_rgba8(width: usize, height: usize, data: &[u8]) -> ImageTexture {
+    let decode = |c: u8| {
+      let c = c as f64 / 255.;
+      if c <= 0.04045 {
+        c / 12.92
+      } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+      }
+    };
+    let pixels = data
+      .chunks_exact(4)
+      .map(|p| Color::new(decode(p[0]), decode(p[1]), decode(p[2])))
+      .collect();
+    Self::new(width, height, pixels)
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn pixel(&self, i: usize, j: usize) -> Color {
+    self
+      .pixels
+      .get(j * self.width + i)
+      .copied()
+      .unwrap_or_default()
+  }
+}
+
+impl Texture for ImageTexture {
+  fn value(&self, u: f64, v: f64, _: Vec3) -> Color {
+    if self.width == 0 || self.height == 0 {
+      return Color::new(0., 1., 1.);
+    }
+    let unit = Interval::new(0., 1.);
+    let u = unit.clamp(u);
+    let v = 1. - unit.clamp(v);
+    let i = ((u * self.width as f64) as usize).min(self.width - 1);
+    let j = ((v * self.height as f64) as usize).min(self.height - 1);
+    self.pixel(i, j)
+  }
+}
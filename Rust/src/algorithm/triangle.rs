@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use super::aabb::Aabb;
+use super::color::Color;
+use super::direction_cone::DirectionCone;
+use super::hittable::{HitRecord, Hittable};
+use super::interval::Interval;
+use super::material::Material;
+use super::random;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+#[derive(Clone)]
+pub struct Triangle {
+  pub vertices: [Vec3; 3],
+  pub uvs: [(f64, f64); 3],
+  pub material: Arc<dyn Material>,
+  normal: Vec3,
+  tangent: Vec3,
+}
+
+impl Triangle {
+  pub fn new(a: Vec3, b: Vec3, c: Vec3, material: Arc<dyn Material>) -> Triangle {
+    Self::with_uvs([a, b, c], [(0., 0.), (1., 0.), (0., 1.)], material)
+  }
+
+  pub fn with_uvs(
+    vertices: [Vec3; 3],
+    uvs: [(f64, f64); 3],
+    material: Arc<dyn Material>,
+  ) -> Triangle {
+    let [a, b, c] = vertices;
+    let (e1, e2) = (b - a, c - a);
+    let normal = Vec3::cross(e1, e2).normalization();
+
+    let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+    let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+    let determinant = du1 * dv2 - du2 * dv1;
+    let tangent = if determinant.abs() > 1e-12 {
+      (dv2 * e1 - dv1 * e2) / determinant
+    } else {
+      e1
+    };
+
+    Triangle {
+      vertices,
+      uvs,
+      material,
+      normal,
+      tangent,
+    }
+  }
+}
+
+impl Hittable for Triangle {
+  fn hit(&self, ray: Ray, interval: Interval) -> Option<HitRecord<'_>> {
+    let [a, b, c] = self.vertices;
+    let (e1, e2) = (b - a, c - a);
+    let p = Vec3::cross(ray.direction, e2);
+    let determinant = e1.dot(p);
+    if determinant.abs() < 1e-12 {
+      return None;
+    }
+    let inv_determinant = 1. / determinant;
+    let s = ray.origin - a;
+    let b1 = s.dot(p) * inv_determinant;
+    if !(0. ..=1.).contains(&b1) {
+      return None;
+    }
+    let q = Vec3::cross(s, e1);
+    let b2 = ray.direction.dot(q) * inv_determinant;
+    if b2 < 0. || b1 + b2 > 1. {
+      return None;
+    }
+    let t = e2.dot(q) * inv_determinant;
+    if !interval.surrounds(t) {
+      return None;
+    }
+
+    let b0 = 1. - b1 - b2;
+    let mut record = HitRecord::new(ray.at(t), t, self.normal, ray, self.material.as_ref());
+    record.u = b0 * self.uvs[0].0 + b1 * self.uvs[1].0 + b2 * self.uvs[2].0;
+    record.v = b0 * self.uvs[0].1 + b1 * self.uvs[1].1 + b2 * self.uvs[2].1;
+    record.set_tangent(self.tangent);
+    Some(record)
+  }
+
+  fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f64 {
+    let Some(record) = self.hit(Ray::new(origin, direction), Interval::new(0.001, f64::INFINITY))
+    else {
+      return 0.;
+    };
+    let distance_squared = record.t * record.t * direction.len_squared();
+    let cosine = (direction.dot(self.normal) / direction.len()).abs();
+    distance_squared / (cosine * self.area())
+  }
+
+  fn random(&self, origin: Vec3) -> Vec3 {
+    let mut rng = random::rng();
+    let r1 = rng.random::<f64>().sqrt();
+    let r2 = rng.random::<f64>();
+    let [a, b, c] = self.vertices;
+    let point = (1. - r1) * a + r1 * (1. - r2) * b + r1 * r2 * c;
+    point - origin
+  }
+
+  fn area(&self) -> f64 {
+    let [a, b, c] = self.vertices;
+    0.5 * Vec3::cross(b - a, c - a).len()
+  }
+
+  fn emission(&self) -> Color {
+    self.material.emission()
+  }
+
+  fn bounding_box(&self) -> Option<Aabb> {
+    let [a, b, c] = self.vertices;
+    Some(Aabb::new(a, b).union(&Aabb::point(c)).pad(1e-4))
+  }
+
+  fn normal_bounds(&self) -> DirectionCone {
+    DirectionCone::new(self.normal, 1.)
+  }
+}
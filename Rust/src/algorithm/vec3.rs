@@ -1,6 +1,8 @@
 use rand::Rng;
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
 
+use super::random;
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Vec3 {
   pub x: f64,
@@ -58,6 +60,10 @@ impl Vec3 {
     self.x.abs() < border && self.y.abs() < border && self.z.abs() < border
   }
 
+  pub fn exp(&self) -> Vec3 {
+    Vec3::new(self.x.exp(), self.y.exp(), self.z.exp())
+  }
+
   #[inline]
   pub fn reflect(&self, normal: Vec3) -> Vec3 {
     *self - 2.0 * self.dot(normal) * normal
@@ -139,12 +145,12 @@ impl Sub for Vec3 {
 
 impl Vec3 {
   pub fn random() -> Vec3 {
-    let mut rng = rand::rng();
+    let mut rng = random::rng();
     Vec3::new(rng.random::<f64>(), rng.random::<f64>(), rng.random::<f64>())
   }
 
   pub fn random_range(min: f64, max: f64) -> Vec3 {
-    let mut rng = rand::rng();
+    let mut rng = random::rng();
     Vec3::new(rng.random_range(min..max), rng.random_range(min..max), rng.random_range(min..max))
   }
 
@@ -159,7 +165,7 @@ impl Vec3 {
   }
 
   pub fn random_in_unit_disk() -> Vec3 {
-    let mut rng = rand::rng();
+    let mut rng = random::rng();
     loop {
       let p = Vec3::new(rng.random_range(-1f64..1f64), rng.random_range(-1f64..1f64), 0.);
       if p.len_squared() < 1. {
@@ -168,6 +174,25 @@ impl Vec3 {
     }
   }
 
+  pub fn random_cosine_direction() -> Vec3 {
+    let mut rng = random::rng();
+    let r1 = rng.random::<f64>();
+    let r2 = rng.random::<f64>();
+    let phi = 2. * std::f64::consts::PI * r1;
+    let r = r2.sqrt();
+    Vec3::new(phi.cos() * r, phi.sin() * r, (1. - r2).sqrt())
+  }
+
+  pub fn random_in_cone(cos_theta_max: f64) -> Vec3 {
+    let mut rng = random::rng();
+    let r1 = rng.random::<f64>();
+    let r2 = rng.random::<f64>();
+    let z = 1. + r2 * (cos_theta_max - 1.);
+    let phi = 2. * std::f64::consts::PI * r1;
+    let r = (1. - z * z).max(0.).sqrt();
+    Vec3::new(phi.cos() * r, phi.sin() * r, z)
+  }
+
   pub fn random_on_hemisphere(normal: Vec3) -> Vec3 {
     let on_unit_sphere = Vec3::random_unit_vector();
     if on_unit_sphere.dot(normal) > 0.0 {
@@ -1,5 +1,5 @@
 pub mod algorithm;
-use algorithm::generator::generate_raw_data;
+use algorithm::generator::{generate_raw_data, generate_raw_data_with_threads};
 
 #[allow(non_camel_case_types)]
 #[swift_bridge::bridge]
@@ -7,5 +7,7 @@ mod ffi {
   extern "Rust" {
     #[swift_bridge(swift_name = "generateRawData")]
     fn generate_raw_data(width: usize, height: usize) -> Vec<u8>;
+    #[swift_bridge(swift_name = "generateRawDataWithThreads")]
+    fn generate_raw_data_with_threads(width: usize, height: usize, threads: usize) -> Vec<u8>;
   }
 }